@@ -0,0 +1,73 @@
+use crate::{WaypointError, Waypoints};
+
+/// A barrier-like view of a single waypoint, created by [`Waypoints::as_barrier`].
+///
+/// Unlike [`std::sync::Barrier`], the barrier is not reusable: it corresponds to the waypoints
+/// `n..n + count`, so calling [`Barrier::wait`] more than `count` times returns an `Err` unless
+/// the [`Waypoints`] are reset.
+#[derive(Debug, Clone, Copy)]
+pub struct Barrier<'a> {
+    waypoints: &'a Waypoints,
+    n: usize,
+    count: usize,
+}
+
+impl<'a> Barrier<'a> {
+    pub(crate) fn new(waypoints: &'a Waypoints, n: usize, count: usize) -> Self {
+        Self {
+            waypoints,
+            n,
+            count,
+        }
+    }
+
+    /// Block until `count` threads have called `wait`.  See [`Waypoints::barrier`].
+    #[track_caller]
+    pub fn wait(&self) -> Result<bool, WaypointError> {
+        self.waypoints.barrier(self.n, self.count)
+    }
+}
+
+/// A barrier that synchronizes the same threads for a fixed number of rounds and then lets them
+/// pass freely, created by [`Waypoints::cyclic_barrier`].
+///
+/// Round `k` corresponds to the waypoints `n + k * count..n + (k + 1) * count`.  Once all rounds
+/// were completed, i.e. once the current number is at least `n + cycles * count`, the barrier is
+/// transparent: [`CyclicBarrier::wait`] returns `Ok(false)` immediately, without waiting or
+/// advancing.  This models synchronizing the first iterations of a loop (e.g. a warm-up) and
+/// letting the rest run free.
+#[derive(Debug, Clone, Copy)]
+pub struct CyclicBarrier<'a> {
+    waypoints: &'a Waypoints,
+    n: usize,
+    count: usize,
+    cycles: usize,
+}
+
+impl<'a> CyclicBarrier<'a> {
+    pub(crate) fn new(waypoints: &'a Waypoints, n: usize, count: usize, cycles: usize) -> Self {
+        Self {
+            waypoints,
+            n,
+            count,
+            cycles,
+        }
+    }
+
+    /// Block until `count` threads have called `wait` in the current round, or return `Ok(false)`
+    /// immediately once all rounds were completed.  Each thread calls this once per round.  See
+    /// [`Waypoints::barrier`] for the `Result`.
+    #[track_caller]
+    pub fn wait(&self) -> Result<bool, WaypointError> {
+        if self.count == 0 {
+            return self.waypoints.barrier(self.n, 0);
+        }
+        let current = self.waypoints.passed_count();
+        let round = current.saturating_sub(self.n) / self.count;
+        if round >= self.cycles {
+            return Ok(false);
+        }
+        self.waypoints
+            .barrier(self.n + round * self.count, self.count)
+    }
+}
@@ -0,0 +1,186 @@
+use crate::{Sleeper, State, Waypoints};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Configures and creates [`Waypoints`].
+///
+/// ```
+/// use waypoints::Waypoints;
+///
+/// let w = Waypoints::builder().capacity(64).build_arc();
+/// w.point(0, None).unwrap();
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Builder {
+    capacity: usize,
+    config: Config,
+}
+
+// settings of `Waypoints` that do not change after they are built
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Config {
+    pub(crate) expected_final: Option<usize>,
+    pub(crate) phase_parties: Option<usize>,
+    pub(crate) strict_sequence: bool,
+    pub(crate) soft_order: bool,
+    pub(crate) max_wait: Option<Duration>,
+    pub(crate) max_head_start_target: Option<Duration>,
+    pub(crate) head_start_ratio: Option<f64>,
+    pub(crate) on_timeout: TimeoutAction,
+    pub(crate) sleeper: Option<Arc<dyn Sleeper>>,
+    pub(crate) dump_after: Option<DumpAfter>,
+}
+
+/// What a thread does when its wait for a waypoint times out, configured with
+/// [`Builder::on_timeout`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutAction {
+    /// Return an [`ErrorKind::TimedOut`](crate::ErrorKind::TimedOut).
+    #[default]
+    ReturnError,
+    /// Print the report of [`Waypoints::dump`] and a backtrace of the timed out thread to
+    /// stderr, then abort the process with [`std::process::abort`].
+    AbortWithBacktrace,
+}
+
+// the escalation of a long wait configured with `Builder::dump_after`
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DumpAfter {
+    pub(crate) threshold: Duration,
+    // time out after printing the report instead of waiting further
+    pub(crate) fail: bool,
+}
+
+impl Builder {
+    /// Create a `Builder` with the default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reserve space for the events of `n` waypoints recorded with [`Waypoints::record`], so that
+    /// recording the first `n` passages does not grow the event log and perturb the timing of a
+    /// test.  Other bookkeeping, such as the name of the passing thread in each event, still
+    /// allocates.
+    pub fn capacity(mut self, n: usize) -> Self {
+        self.capacity = n;
+        self
+    }
+
+    /// Require the sequence to reach waypoint `n` (i.e. [`Waypoints::is_complete`] with `n`)
+    /// before the `Waypoints` are dropped.  Otherwise dropping the `Waypoints` panics, which
+    /// catches threads that returned before passing their waypoints.  The check is skipped if the
+    /// dropping thread is already panicking.
+    pub fn expected_final(mut self, n: usize) -> Self {
+        self.config.expected_final = Some(n);
+        self
+    }
+
+    /// Set the number of threads that must call [`Waypoints::point_phase`] before any of them
+    /// proceeds to the next phase.
+    pub fn phase_parties(mut self, count: usize) -> Self {
+        self.config.phase_parties = Some(count);
+        self
+    }
+
+    /// Forbid advancing the current number by more than one at a time, so waypoints are passed
+    /// strictly as 0, 1, 2, ...  With this enabled, [`Waypoints::set`] returns an
+    /// [`ErrorKind::Gap`](crate::ErrorKind::Gap) instead of skipping waypoints, and
+    /// [`Waypoints::range_parked`] passes one waypoint per thread.  Moving the current number back
+    /// with [`Waypoints::set_force`] is still allowed.
+    pub fn strict_sequence(mut self, enable: bool) -> Self {
+        self.config.strict_sequence = enable;
+        self
+    }
+
+    /// Limit how long any waypoint of these `Waypoints` may wait to be passed, unless an explicit
+    /// deadline or a timeout of [`Waypoints::with_timeout`] applies.  This takes precedence over
+    /// [`set_default_max_wait`](crate::set_default_max_wait).
+    pub fn max_wait(mut self, max: Duration) -> Self {
+        self.config.max_wait = Some(max);
+        self
+    }
+
+    /// Clamp the time at which the next waypoint may pass to at most `max` after the waypoint that
+    /// scheduled it, however large its head start is or however head starts, gaps and paces add
+    /// up.  This guards against a runaway schedule stalling a test suite for minutes.
+    pub fn max_head_start_target(mut self, max: Duration) -> Self {
+        self.config.max_head_start_target = Some(max);
+        self
+    }
+
+    /// Give each waypoint passed without a head start a head start of `f` times the dwell of the
+    /// previous step, i.e. the time between passing the previous waypoint and passing this one.
+    /// Margins then scale with the speed of the machine, so a slow or overloaded CI runner
+    /// automatically gets proportionally larger ones without hand-tuned durations.  An explicit
+    /// head start (including [`Waypoints::set_next_head_start`]) takes precedence, and the first
+    /// waypoint after creating or resetting the `Waypoints` has no previous step.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f` is negative or not finite.
+    pub fn head_start_ratio(mut self, f: f64) -> Self {
+        assert!(
+            f.is_finite() && f >= 0.0,
+            "head start ratio must be finite and non-negative"
+        );
+        self.config.head_start_ratio = Some(f);
+        self
+    }
+
+    /// Choose what a thread does when its wait for a waypoint times out, whether the deadline is
+    /// explicit, set by [`Waypoints::with_timeout`], by [`Self::max_wait`] or
+    /// [`set_default_max_wait`](crate::set_default_max_wait), or by [`Self::dump_after`] with
+    /// `fail`.  [`TimeoutAction::AbortWithBacktrace`] is a heavy hammer for catching a rare hang
+    /// on CI in the act instead of returning a clean error.  Only the backtrace of the timed out
+    /// thread can be captured with std; the backtraces of the other threads are in the core dump
+    /// of the abort, where core dumps are enabled.  The slices a
+    /// [`WeakWaypoints`](crate::WeakWaypoints) waits in do not count as timeouts.
+    pub fn on_timeout(mut self, action: TimeoutAction) -> Self {
+        self.config.on_timeout = action;
+        self
+    }
+
+    /// Print the report of [`Waypoints::dump`] to stderr once a thread has waited for a waypoint
+    /// for longer than `threshold`.  The thread then keeps waiting, or returns
+    /// [`ErrorKind::TimedOut`](crate::ErrorKind::TimedOut) if `fail` is `true`.  This turns a
+    /// silent hang (e.g. a CI run that times out) into one that shows what every thread waits
+    /// for.  The report is printed once per wait, and not at all if an explicit deadline or
+    /// timeout ends the wait first.
+    pub fn dump_after(mut self, threshold: Duration, fail: bool) -> Self {
+        self.config.dump_after = Some(DumpAfter { threshold, fail });
+        self
+    }
+
+    /// Pass waypoints out of order instead of waiting for them: a waypoint that is not the current
+    /// one is passed anyway, advancing the current number by one, and logs a warning
+    /// (`expected to pass n but sequence at current`) if the `log` feature is enabled.  A thread of
+    /// [`Waypoints::range_parked`] passed out of order releases no other threads.  This is a
+    /// stepping stone for introducing an order into a flaky test: the number of waypoints passed
+    /// out of order is counted in [`Stats::out_of_order`](crate::Stats::out_of_order), which
+    /// quantifies how often the undesired order happens before it is enforced.
+    pub fn soft_order(mut self, enable: bool) -> Self {
+        self.config.soft_order = enable;
+        self
+    }
+
+    /// Wait out head starts with `sleeper` instead of the
+    /// [`DefaultSleeper`](crate::DefaultSleeper).
+    pub fn sleeper(mut self, sleeper: impl Sleeper + 'static) -> Self {
+        self.config.sleeper = Some(Arc::new(sleeper));
+        self
+    }
+
+    /// Create the configured `Waypoints`.
+    pub fn build(self) -> Waypoints {
+        let state = State {
+            events: Vec::with_capacity(self.capacity),
+            ..State::default()
+        };
+        Waypoints::from_parts(state, self.config)
+    }
+
+    /// Create the configured `Waypoints` wrapped in an [`Arc`].
+    pub fn build_arc(self) -> Arc<Waypoints> {
+        Arc::new(self.build())
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{ErrorKind, WaypointError, Waypoints};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+/// Represents waypoints ordered by a dependency graph rather than a total order.
+///
+/// Each waypoint is a node identified by a number, and [`DagWaypoints::depends`] declares which
+/// nodes have to be passed before a node may be passed.  Nodes without a path between them are
+/// unordered, so "C follows both A and B, but A and B may pass in either order" is expressed
+/// without forcing an artificial order between A and B, which a single counter like that of
+/// [`Waypoints`] cannot avoid.  Every node can be passed once.
+///
+/// ```
+/// use waypoints::DagWaypoints;
+///
+/// let (a, b, c) = (0, 1, 2);
+/// let w = DagWaypoints::new();
+/// w.depends(c, &[a, b]);
+/// std::thread::scope(|s| {
+///     s.spawn(|| w.point(c).unwrap());
+///     s.spawn(|| w.point(b).unwrap());
+///     s.spawn(|| w.point(a).unwrap());
+/// });
+/// assert!(w.is_passed(c));
+/// ```
+#[derive(Debug, Default)]
+pub struct DagWaypoints {
+    state: Mutex<State>,
+    cv: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    // the nodes each node depends on
+    deps: HashMap<usize, HashSet<usize>>,
+    passed: HashSet<usize>,
+}
+
+impl State {
+    // whether `to` can be reached from `from` by following dependencies
+    fn reaches(&self, from: usize, to: usize) -> bool {
+        let mut stack = vec![from];
+        let mut visited = HashSet::new();
+        while let Some(node) = stack.pop() {
+            if node == to {
+                return true;
+            }
+            if visited.insert(node) {
+                stack.extend(self.deps.get(&node).into_iter().flatten());
+            }
+        }
+        false
+    }
+
+    fn is_ready(&self, node: usize) -> bool {
+        let mut deps = self.deps.get(&node).into_iter().flatten();
+        deps.all(|d| self.passed.contains(d))
+    }
+}
+
+impl DagWaypoints {
+    /// Create `DagWaypoints` without dependencies.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create `DagWaypoints` wrapped in an [`Arc`].
+    pub fn new_arc() -> Arc<Self> {
+        Arc::new(Self::new())
+    }
+
+    /// Declare that `node` may only be passed once all of `deps` were passed.  Dependencies
+    /// accumulate over calls.  Threads already waiting for `node` wait for the new dependencies
+    /// as well.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a dependency would create a cycle, since the nodes of a cycle could never be
+    /// passed.  All of `deps` are checked before any is added, so the dependencies are unchanged
+    /// after the panic.
+    pub fn depends(&self, node: usize, deps: &[usize]) {
+        let mut state_lck = self.state_lck();
+        // the new dependencies all start at `node`, so only an existing path can close a cycle
+        if let Some(&dep) = deps.iter().find(|&&dep| state_lck.reaches(dep, node)) {
+            // do not poison the lock
+            drop(state_lck);
+            panic!("dependency of {} on {} would create a cycle", node, dep);
+        }
+        state_lck.deps.entry(node).or_default().extend(deps);
+    }
+
+    /// Pass `node` once all the nodes it depends on were passed.  The `Result` is an
+    /// [`ErrorKind::AlreadyPassed`] containing `node` if it was already passed.
+    #[track_caller]
+    pub fn point(&self, node: usize) -> Result<(), WaypointError> {
+        let state_lck = self.cv.wait_while(self.state_lck(), |st| {
+            !st.passed.contains(&node) && !st.is_ready(node)
+        });
+        let mut state_lck = Waypoints::into_guard(state_lck);
+        if !state_lck.passed.insert(node) {
+            return Err(ErrorKind::AlreadyPassed(node).into());
+        }
+        drop(state_lck);
+        self.cv.notify_all();
+        Ok(())
+    }
+
+    /// Whether `node` was passed.
+    pub fn is_passed(&self, node: usize) -> bool {
+        self.state_lck().passed.contains(&node)
+    }
+
+    fn state_lck(&self) -> MutexGuard<'_, State> {
+        Waypoints::into_guard(self.state.lock())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_order() {
+        let w = DagWaypoints::new_arc();
+        w.depends(2, &[0, 1]);
+        let t = {
+            let w = w.clone();
+            std::thread::spawn(move || w.point(2))
+        };
+        w.point(1).unwrap();
+        assert!(!w.is_passed(2));
+        w.point(0).unwrap();
+        assert_eq!(t.join().unwrap(), Ok(()));
+        assert_eq!(w.point(0), Err(ErrorKind::AlreadyPassed(0).into()));
+    }
+
+    #[test]
+    #[should_panic(expected = "cycle")]
+    fn cycle() {
+        let w = DagWaypoints::new();
+        w.depends(1, &[0]);
+        w.depends(2, &[1]);
+        w.depends(0, &[2]);
+    }
+
+    #[test]
+    fn cycle_unchanged() {
+        let w = DagWaypoints::new();
+        w.depends(1, &[0]);
+        let res = std::panic::catch_unwind(|| w.depends(0, &[2, 1]));
+        assert!(res.is_err());
+        assert!(!w.state.is_poisoned());
+        w.point(0).unwrap();
+        w.point(1).unwrap();
+    }
+}
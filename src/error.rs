@@ -0,0 +1,151 @@
+use std::fmt;
+use std::panic::Location;
+
+/// The error returned when a waypoint could not be passed, with the source location of the call
+/// that failed.
+///
+/// Errors compare equal if their [`ErrorKind`]s are equal, regardless of their locations, so an
+/// expected error can be written as `Err(ErrorKind::AlreadyPassed(1).into())`.
+#[derive(Debug, Clone, Copy)]
+pub struct WaypointError {
+    kind: ErrorKind,
+    location: &'static Location<'static>,
+}
+
+impl WaypointError {
+    /// The reason the waypoint could not be passed.
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The source location of the call that failed.
+    pub fn location(&self) -> &'static Location<'static> {
+        self.location
+    }
+}
+
+impl From<ErrorKind> for WaypointError {
+    /// Attach the location of the caller to `kind`.
+    #[track_caller]
+    fn from(kind: ErrorKind) -> Self {
+        Self {
+            kind,
+            location: Location::caller(),
+        }
+    }
+}
+
+impl PartialEq for WaypointError {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
+}
+
+impl Eq for WaypointError {}
+
+impl fmt::Display for WaypointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.kind, self.location)
+    }
+}
+
+impl std::error::Error for WaypointError {}
+
+/// The reason a waypoint could not be passed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// Another caller already passed the waypoint.  Contains the current waypoint number.
+    AlreadyPassed(usize),
+    /// Another caller already passed the `(phase, step)` waypoint of
+    /// [`PhasedWaypoints`](crate::PhasedWaypoints), or its phase is over.  Contains the current
+    /// `(phase, step)`.
+    PhaseAlreadyPassed {
+        /// The current phase.
+        phase: usize,
+        /// The current step within the phase.
+        step: usize,
+    },
+    /// The deadline passed before the waypoint was reached.  If `end_current` is greater than
+    /// `start_current` the sequence progressed, only too slowly; if they are equal it stalled.
+    TimedOut {
+        /// The current waypoint number when the wait started.
+        start_current: usize,
+        /// The current waypoint number when the deadline passed.
+        end_current: usize,
+        /// The waypoint number that was waited for.
+        expected: usize,
+    },
+    /// The state was inconsistent with the waypoint after waiting for it, which indicates a bug in
+    /// [`Waypoints`](crate::Waypoints).  This is only returned in release builds: debug builds
+    /// panic with the requested range, the current waypoint number and the time at which the
+    /// next waypoint may pass.  Contains the current waypoint number.
+    Inconsistent(usize),
+    /// The range of waypoints had a lower bound greater than its upper bound, so it could never be
+    /// reached.
+    InvalidRange {
+        /// The inclusive lower bound.
+        l: usize,
+        /// The exclusive upper bound.
+        h: usize,
+    },
+    /// Advancing would have skipped waypoints while
+    /// [`Builder::strict_sequence`](crate::Builder::strict_sequence) is enabled.
+    Gap {
+        /// The current waypoint number.
+        from: usize,
+        /// The waypoint number the sequence would have advanced to.
+        to: usize,
+    },
+    /// The [`Waypoints`](crate::Waypoints) of a [`WeakWaypoints`](crate::WeakWaypoints) handle were
+    /// dropped.
+    Gone,
+    /// [`Waypoints::set`](crate::Waypoints::set) would have moved the current number back.
+    Backward {
+        /// The current waypoint number.
+        from: usize,
+        /// The waypoint number the sequence would have moved back to.
+        to: usize,
+    },
+    /// The sequence was cancelled with [`Waypoints::cancel`](crate::Waypoints::cancel), before or
+    /// while waiting for the waypoint.
+    Cancelled,
+    /// The sequence was reset since the caller of
+    /// [`Waypoints::point_epoch`](crate::Waypoints::point_epoch) observed its epoch.
+    StaleEpoch {
+        /// The epoch the caller expected.
+        expected: u64,
+        /// The current epoch.
+        current: u64,
+    },
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AlreadyPassed(n) => write!(f, "waypoint already passed (current waypoint {})", n),
+            Self::PhaseAlreadyPassed { phase, step } => write!(
+                f,
+                "waypoint already passed (current phase {}, step {})",
+                phase, step
+            ),
+            Self::TimedOut {
+                start_current,
+                end_current,
+                expected,
+            } => write!(
+                f,
+                "timed out waiting for waypoint {} (current waypoint {}, was {})",
+                expected, end_current, start_current
+            ),
+            Self::Inconsistent(n) => write!(f, "inconsistent state (current waypoint {})", n),
+            Self::InvalidRange { l, h } => write!(f, "invalid waypoint range {}..{}", l, h),
+            Self::Gap { from, to } => write!(f, "skipped waypoints from {} to {}", from, to),
+            Self::Gone => write!(f, "waypoints were dropped"),
+            Self::Backward { from, to } => write!(f, "moved back from waypoint {} to {}", from, to),
+            Self::Cancelled => write!(f, "waypoints were cancelled"),
+            Self::StaleEpoch { expected, current } => {
+                write!(f, "stale epoch {} (current epoch {})", expected, current)
+            }
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use crate::Waypoints;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// A future that resolves once a waypoint is reached, created by [`Waypoints::wait_future`].
+///
+/// The first poll spawns a helper thread that waits on the [`Waypoints`] like a thread calling
+/// [`Waypoints::point`] would, and wakes the most recently registered [`Waker`] once the current
+/// number reaches the waypoint.  Dropping the future before the waypoint is reached stops the
+/// helper thread.
+#[derive(Debug)]
+pub struct WaitFuture {
+    waypoints: Arc<Waypoints>,
+    n: usize,
+    shared: Arc<Shared>,
+    spawned: bool,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    // `Some` while the waypoint was not reached
+    waker: Mutex<Option<Option<Waker>>>,
+    cancelled: AtomicBool,
+}
+
+impl WaitFuture {
+    pub(crate) fn new(waypoints: Arc<Waypoints>, n: usize) -> Self {
+        let shared = Shared {
+            waker: Mutex::new(Some(None)),
+            cancelled: AtomicBool::new(false),
+        };
+        Self {
+            waypoints,
+            n,
+            shared: Arc::new(shared),
+            spawned: false,
+        }
+    }
+
+    fn spawn(&self) {
+        let waypoints = self.waypoints.clone();
+        let shared = self.shared.clone();
+        let n = self.n;
+        std::thread::spawn(move || {
+            let mut state_lck = waypoints.state_lck();
+            let ticket = state_lck.register_waiter(n);
+            let state_lck = waypoints.cv.wait_while(state_lck, |st| {
+                st.n < n && !shared.cancelled.load(Ordering::SeqCst)
+            });
+            Waypoints::into_guard(state_lck).waiters.remove(&ticket);
+
+            let waker = Waypoints::into_guard(shared.waker.lock()).take();
+            if let Some(Some(waker)) = waker {
+                waker.wake();
+            }
+        });
+    }
+}
+
+impl Future for WaitFuture {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if !self.spawned {
+            self.spawned = true;
+            self.spawn();
+        }
+        match &mut *Waypoints::into_guard(self.shared.waker.lock()) {
+            Some(waker) => {
+                *waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+            None => Poll::Ready(()),
+        }
+    }
+}
+
+impl Drop for WaitFuture {
+    fn drop(&mut self) {
+        if self.spawned {
+            self.shared.cancelled.store(true, Ordering::SeqCst);
+            // lock the state so the helper thread is either waiting or yet to check the flag
+            drop(self.waypoints.state_lck());
+            self.waypoints.cv.notify_all();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::task::Wake;
+    use std::thread::Thread;
+
+    struct Unpark(Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(f: F) -> F::Output {
+        let waker = Waker::from(Arc::new(Unpark(std::thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut f = Box::pin(f);
+        loop {
+            match f.as_mut().poll(&mut cx) {
+                Poll::Ready(out) => return out,
+                Poll::Pending => std::thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn wait_future() {
+        let w = Waypoints::new_arc();
+        let t = {
+            let w = w.clone();
+            std::thread::spawn(move || {
+                w.point(0, None).unwrap();
+                w.point(1, None).unwrap();
+            })
+        };
+        block_on(w.wait_future(2));
+        assert_eq!(w.passed_count(), 2);
+        t.join().unwrap();
+    }
+
+    #[test]
+    fn drop_wait_future() {
+        let w = Waypoints::new_arc();
+        let mut f = Box::pin(w.wait_future(1));
+        let waker = Waker::from(Arc::new(Unpark(std::thread::current())));
+        assert!(f
+            .as_mut()
+            .poll(&mut Context::from_waker(&waker))
+            .is_pending());
+        while w.waiters() == 0 {
+            std::thread::yield_now();
+        }
+        drop(f);
+        while w.waiters() != 0 {
+            std::thread::yield_now();
+        }
+    }
+}
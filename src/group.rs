@@ -0,0 +1,77 @@
+use crate::Waypoints;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Arc;
+
+/// A set of named [`Waypoints`] with a shared lifecycle, e.g. the sequences of a test fixture.
+///
+/// ```
+/// use waypoints::WaypointsGroup;
+///
+/// let group = WaypointsGroup::new(["reader", "writer"]);
+/// group.get("reader").unwrap().point(0, None).unwrap();
+/// group.reset_all();
+/// assert_eq!(group.get("reader").unwrap().passed_count(), 0);
+/// ```
+#[derive(Debug, Default)]
+pub struct WaypointsGroup {
+    waypoints: HashMap<String, Arc<Waypoints>>,
+}
+
+impl WaypointsGroup {
+    /// Create a group with new `Waypoints` for each of `names`.
+    pub fn new(names: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let waypoints = names
+            .into_iter()
+            .map(|name| (name.into(), Waypoints::new_arc()))
+            .collect();
+        Self { waypoints }
+    }
+
+    /// The `Waypoints` named `name`, if they are part of the group.
+    pub fn get(&self, name: &str) -> Option<&Arc<Waypoints>> {
+        self.waypoints.get(name)
+    }
+
+    /// Reset all `Waypoints` of the group with [`Waypoints::reset`], e.g. between test cases.
+    pub fn reset_all(&self) {
+        self.waypoints.values().for_each(|w| w.reset());
+    }
+
+    /// Print the report of [`Waypoints::dump`] for each `Waypoints` of the group to stderr,
+    /// ordered by name.
+    pub fn dump_all(&self) {
+        eprint!("{}", self.report());
+    }
+
+    fn report(&self) -> String {
+        let mut names: Vec<_> = self.waypoints.keys().collect();
+        names.sort();
+        let mut report = String::new();
+        for name in names {
+            writeln!(report, "{}:", name).expect("writing to a `String` does not fail");
+            report.push_str(&self.waypoints[name].report());
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report() {
+        let group = WaypointsGroup::new(["b", "a"]);
+        group.get("b").unwrap().point(0, None).unwrap();
+        let report = group.report();
+        let a = report.find("a:\n").unwrap();
+        let b = report.find("b:\n").unwrap();
+        assert!(a < b);
+        assert_eq!(
+            report[b..],
+            format!("b:\n{}", group.get("b").unwrap().report())
+        );
+        assert!(group.get("c").is_none());
+    }
+}
@@ -0,0 +1,53 @@
+use crate::Waypoints;
+
+/// A guard that passes a waypoint when it is dropped, created by [`Waypoints::advance_on_drop`].
+#[must_use = "the waypoint is passed as soon as the guard is dropped"]
+#[derive(Debug)]
+pub struct AdvanceOnDrop<'a> {
+    waypoints: &'a Waypoints,
+    n: usize,
+}
+
+impl<'a> AdvanceOnDrop<'a> {
+    pub(crate) fn new(waypoints: &'a Waypoints, n: usize) -> Self {
+        Self { waypoints, n }
+    }
+}
+
+impl Drop for AdvanceOnDrop<'_> {
+    fn drop(&mut self) {
+        // the waypoint may have been passed out of band, e.g. after a `set`
+        let _ = self.waypoints.point(self.n, None);
+    }
+}
+
+/// A guard that panics when it is dropped before the sequence reached a waypoint, created by
+/// [`Waypoints::expect_all`].
+#[must_use = "the sequence is checked as soon as the guard is dropped"]
+#[derive(Debug)]
+pub struct ExpectAll<'a> {
+    waypoints: &'a Waypoints,
+    n: usize,
+}
+
+impl<'a> ExpectAll<'a> {
+    pub(crate) fn new(waypoints: &'a Waypoints, n: usize) -> Self {
+        Self { waypoints, n }
+    }
+}
+
+impl Drop for ExpectAll<'_> {
+    fn drop(&mut self) {
+        // panicking again would abort and hide the original panic
+        if std::thread::panicking() {
+            return;
+        }
+        let current = self.waypoints.current();
+        if current < self.n {
+            panic!(
+                "waypoint sequence stalled at {}, expected {}",
+                current, self.n
+            );
+        }
+    }
+}
@@ -0,0 +1,36 @@
+/// Identifies a waypoint by its number, allowing [`Waypoints::point`](crate::Waypoints::point)
+/// (and its variants) to take named steps instead of plain numbers.
+///
+/// With the `derive` feature, `#[derive(Waypoint)]` implements the trait for an enum with unit
+/// variants, numbering the variants in the order in which they are declared:
+///
+/// ```
+/// # #[cfg(feature = "derive")]
+/// # {
+/// use waypoints::{Waypoint, Waypoints};
+///
+/// #[derive(Clone, Copy, Waypoint)]
+/// enum Step {
+///     Prepare,
+///     Commit,
+/// }
+///
+/// let w = Waypoints::new();
+/// w.point(Step::Prepare, None).unwrap();
+/// w.point(Step::Commit, None).unwrap();
+/// assert_eq!(Step::Commit.ordinal(), 1);
+/// # }
+/// ```
+///
+/// The macro lives in the `waypoints-derive` crate, a procedural macro crate without
+/// dependencies, which the `derive` feature re-exports.
+pub trait Waypoint {
+    /// The number of the waypoint.
+    fn ordinal(&self) -> usize;
+}
+
+impl Waypoint for usize {
+    fn ordinal(&self) -> usize {
+        *self
+    }
+}
@@ -64,7 +64,83 @@
 //! assert_eq!(obs, (0..6).into_iter().collect::<Vec<_>>());
 //! ```
 //!
+//! # Scoped threads
+//!
+//! All methods of [`Waypoints`] take `&self`, so with [`std::thread::scope`] the waypoints can
+//! live on the stack and be borrowed by each thread instead of being wrapped in an [`Arc`].
+//!
+//! ```
+//! use waypoints::Waypoints;
+//! use std::sync::Mutex;
+//!
+//! let obs = Mutex::new(Vec::new());
+//! let w = Waypoints::new();
+//!
+//! std::thread::scope(|s| {
+//!     s.spawn(|| {
+//!         w.point(1, None).unwrap();
+//!         obs.lock().unwrap().push(1);
+//!     });
+//!     s.spawn(|| {
+//!         obs.lock().unwrap().push(0);
+//!         w.point(0, None).unwrap();
+//!     });
+//! });
+//!
+//! assert_eq!(obs.into_inner().unwrap(), vec![0, 1]);
+//! ```
+//!
+//! # Thread pools
+//!
+//! Waypoints do not depend on the identity of the threads that pass them (except
+//! [`Waypoints::point_reentrant`], which tracks threads by design), so they also order work items
+//! of a thread pool, such as the closures of a `rayon` parallel iterator.  A thread blocked on a
+//! waypoint is a pool thread that cannot run other work items, though: if the waypoints of the
+//! items that would advance the sequence are queued behind blocked items, and every pool thread
+//! is blocked, the sequence deadlocks.  This is guaranteed to happen once more items wait at the
+//! same time than the pool has threads, and a work-stealing pool may also run a later item on a
+//! thread before an earlier one.  Ordering at most as many items as the pool has threads avoids
+//! the deadlock, as does passing the waypoints with a deadline (e.g. [`Waypoints::with_timeout`])
+//! so that a deadlock turns into an error.
+//!
+//! # Features
+//!
+//! - `spin-sleep`: wait out head starts with [`spin_sleep`][spin_sleep_url] instead of
+//!   [`std::thread::sleep`].  The OS scheduler can oversleep short durations considerably (notably
+//!   on Windows); `spin_sleep` sleeps natively for most of the duration and spins for the rest,
+//!   which is much more accurate but keeps a CPU core busy while spinning.
+//!
+//! - `env-order`: add [`Waypoints::from_env_order`], which reads the order in which waypoints are
+//!   passed from the `WAYPOINTS_ORDER` environment variable.  This allows reproducing a particular
+//!   interleaving (e.g. one that failed on CI) without changing the test.
+//!
+//! - `test-internals`: add [`Waypoints::waiter_order`] for asserting the order in which waiting
+//!   threads arrived.  This exposes internal bookkeeping and is not covered by semver.
+//!
+//! - `jitter`: add [`Waypoints::set_jitter`], which delays threads by a random duration after
+//!   they pass a waypoint, and [`Waypoints::fuzz_until`], which reruns a test body with such
+//!   delays until an observation order occurs.  This checks that an interleaving is possible
+//!   rather than forcing it.
+//!
+//! - `derive`: add `#[derive(Waypoint)]`, which implements [`Waypoint`] for an enum with unit
+//!   variants so its variants can be passed to [`Waypoints::point`] as named steps.
+//!
+//! - `log`: emit `log::trace!` messages through the [`log`][log_url] facade whenever a thread
+//!   starts waiting for a waypoint, sleeps for a head start, or passes a waypoint, including the
+//!   requested and the current numbers, and a `log::warn!` message whenever
+//!   [`Builder::soft_order`] passes a waypoint out of order.  This surfaces waypoints in existing
+//!   `env_logger` (or `test-log`) setups with `RUST_LOG=waypoints=trace`.
+//!
+//! - `metrics`: record telemetry through the [`metrics`][metrics_url] facade, so an installed
+//!   exporter shows waypoints in existing dashboards (e.g. during soak tests).  Each passed
+//!   waypoint records the time the thread waited for it in the histogram `waypoints.wait_seconds`
+//!   and increments the counter `waypoints.passed`; every advance sets the gauge
+//!   `waypoints.current` to the current number.  Nothing is compiled in without the feature.
+//!
 //! [repo_url]: https://github.com/trtsl/waypoints
+//! [spin_sleep_url]: https://crates.io/crates/spin_sleep
+//! [metrics_url]: https://crates.io/crates/metrics
+//! [log_url]: https://crates.io/crates/log
 
 #![forbid(unsafe_code)]
 #![warn(
@@ -74,34 +150,525 @@
     rustdoc::broken_intra_doc_links
 )]
 
+mod barrier;
+mod builder;
+mod dag;
+mod error;
+mod future;
+mod group;
+mod guard;
+mod id;
+mod named;
+mod phased;
+mod pool;
+mod probe;
+mod record;
+mod sequence;
+mod sleeper;
+mod snapshot;
+mod stats;
+mod watch;
+mod weak;
+
+pub use barrier::{Barrier, CyclicBarrier};
+use builder::Config;
+pub use builder::{Builder, TimeoutAction};
+pub use dag::DagWaypoints;
+pub use error::{ErrorKind, WaypointError};
+pub use future::WaitFuture;
+pub use group::WaypointsGroup;
+pub use guard::{AdvanceOnDrop, ExpectAll};
+pub use id::Waypoint;
+pub use named::{NamedError, NamedWaypoints};
+pub use phased::PhasedWaypoints;
+pub use pool::{PooledWaypoints, WaypointsPool};
+pub use probe::Probe;
+pub use record::{merge_logs, EventRecord, MergedRecord};
+pub use sequence::{assign, ThreadSequence, Turns};
+pub use sleeper::{DefaultSleeper, Sleeper};
+pub use snapshot::WaypointsSnapshot;
+pub use stats::Stats;
+use watch::Watch;
+pub use watch::{wait_all, wait_any};
+#[cfg(feature = "derive")]
+pub use waypoints_derive::Waypoint;
+pub use weak::WeakWaypoints;
+
+use std::backtrace::Backtrace;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::io;
 use std::ops::Range;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::Receiver;
 use std::sync::Condvar;
-use std::sync::{Arc, LockResult, Mutex, MutexGuard};
+use std::sync::{Arc, LockResult, Mutex, MutexGuard, TryLockError};
+use std::thread::{JoinHandle, ThreadId};
 use std::time::{Duration, Instant};
 
-type Guard<'a> = MutexGuard<'a, (usize, Option<Instant>)>;
+type Guard<'a> = MutexGuard<'a, State>;
+type Callback = Arc<dyn Fn() + Send + Sync>;
+
+thread_local! {
+    // the timeouts set by `Waypoints::with_timeout`, keyed by the address of the `Waypoints`
+    static TIMEOUTS: RefCell<Vec<(usize, Duration)>> = const { RefCell::new(Vec::new()) };
+}
+
+// the maximum wait set by `set_default_max_wait`
+static DEFAULT_MAX_WAIT: Mutex<Option<Duration>> = Mutex::new(None);
+
+/// Limit how long any waypoint of any [`Waypoints`] may wait to be passed, unless a more specific
+/// limit applies: an explicit deadline, a timeout of [`Waypoints::with_timeout`], or the maximum
+/// wait of [`Builder::max_wait`].  A waypoint that is not reached in time returns
+/// [`ErrorKind::TimedOut`].  This is a safety net so that no test hangs indefinitely, set once
+/// (e.g. at the start of each test) without changing the call sites.  `None` removes the limit.
+pub fn set_default_max_wait(max: Option<Duration>) {
+    *Waypoints::into_guard(DEFAULT_MAX_WAIT.lock()) = max;
+}
+
+#[derive(Debug, Default)]
+struct State {
+    // the current waypoint
+    n: usize,
+    // the earliest time at which the next waypoint may be passed
+    target_time: Option<Instant>,
+    // whether passages are added to `events`
+    recording: bool,
+    events: Vec<EventRecord>,
+    // the most recent passages, if `recent_capacity` is not 0
+    recent: VecDeque<EventRecord>,
+    recent_capacity: usize,
+    // the minimum delay between passing waypoint `n - 1` and waypoint `n`, indexed by `n`
+    gaps: Vec<Duration>,
+    // waypoint numbers that `point` treats as a different number
+    aliases: HashMap<usize, usize>,
+    // the position at which `point` passes a waypoint number, if prescribed by an order
+    order: HashMap<usize, usize>,
+    // the positions of waypoints inserted with `Waypoints::insert_before`, in insertion order
+    insertions: Vec<usize>,
+    // the waypoint number each waiting thread waits for, keyed by a ticket in arrival order
+    waiters: BTreeMap<u64, usize>,
+    next_ticket: u64,
+    // the maximum number of `waiters` since creation or `Waypoints::reset_peak`
+    peak_waiters: usize,
+    stats: Stats,
+    // how long the passed waypoints waited, keyed by waypoint number
+    waits: BTreeMap<usize, WaitTimes>,
+    // the phase of `Waypoints::point_phase` and the number of threads that arrived in it
+    phase: usize,
+    phase_arrived: usize,
+    named_phases: Vec<NamedPhase>,
+    // signalled whenever the current number changes
+    watchers: Vec<Arc<Watch>>,
+    // incremented whenever the current number changes
+    generation: u64,
+    // the number of waypoints the current number advanced by, excluding `Waypoints::set`
+    total_advances: u64,
+    // the number of successful passes, for `Waypoints::passed`
+    passes: usize,
+    // the number of threads waiting in `Waypoints::wait_generation`
+    generation_waiters: usize,
+    // replaces the head start of the next advance
+    next_head_start: Option<Duration>,
+    // the time at which the last waypoint was passed, for `Builder::head_start_ratio`
+    last_passed: Option<Instant>,
+    // the tickets of threads parked by `Waypoints::range_parked`, keyed by band
+    bands: HashMap<(usize, usize), BTreeSet<u64>>,
+    // the tickets of parked threads whose waypoints were passed by another thread
+    released: HashSet<u64>,
+    // the ids and tickets of threads waiting in `Waypoints::range_by`, keyed by band
+    ranked: HashMap<(usize, usize), BTreeSet<(u32, u64)>>,
+    // moved forward whenever a waypoint is passed
+    tees: Vec<Arc<Waypoints>>,
+    // the closures of `Waypoints::run_at` that were not called yet
+    run_at: Vec<RunAt>,
+    // the waypoint numbers of the participants declared with `Waypoints::declare_participant`
+    coverage: BTreeSet<usize>,
+    // set by `Waypoints::cancel`, cleared by `Waypoints::reset`
+    cancelled: bool,
+    // incremented by `Waypoints::reset` and `Waypoints::reset_counter_keep_time`
+    epoch: u64,
+    // the waypoint numbers declared with `Waypoints::first_come`
+    first_come: HashSet<usize>,
+    // the thread that passed each waypoint number with `Waypoints::point_reentrant`
+    reentrant: HashMap<usize, ThreadId>,
+    // the delays of `Waypoints::pace_after` that were not applied yet, keyed by waypoint number
+    paces: Vec<(usize, Duration)>,
+    // the maximum delay of `Waypoints::set_jitter` and the state of its random number generator
+    #[cfg(feature = "jitter")]
+    jitter: Option<(Duration, u64)>,
+}
+
+// a range of waypoints defined with `Waypoints::define_phase`
+struct NamedPhase {
+    name: String,
+    rng: Range<usize>,
+    // whether an advance has entered the range
+    entered: bool,
+    on_enter: Vec<Callback>,
+}
+
+impl fmt::Debug for NamedPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NamedPhase")
+            .field("name", &self.name)
+            .field("rng", &self.rng)
+            .field("entered", &self.entered)
+            .field("on_enter", &self.on_enter.len())
+            .finish()
+    }
+}
+
+// the accumulated wait durations of a waypoint number
+#[derive(Debug, Clone, Copy)]
+struct WaitTimes {
+    count: u32,
+    total: Duration,
+    min: Duration,
+    max: Duration,
+}
+
+impl WaitTimes {
+    fn new(wait: Duration) -> Self {
+        Self {
+            count: 1,
+            total: wait,
+            min: wait,
+            max: wait,
+        }
+    }
+
+    fn add(&mut self, wait: Duration) {
+        self.count += 1;
+        self.total += wait;
+        self.min = std::cmp::min(self.min, wait);
+        self.max = std::cmp::max(self.max, wait);
+    }
+}
+
+// what remains of passing a waypoint once the state is unlocked, returned by
+// `Waypoints::pass_locked`
+struct Passage {
+    #[cfg_attr(not(feature = "log"), allow(dead_code))]
+    this: usize,
+    next: usize,
+    now: Instant,
+    target_time_this: Option<Instant>,
+    #[cfg_attr(not(feature = "metrics"), allow(dead_code))]
+    wait: Duration,
+    #[cfg_attr(not(any(feature = "log", feature = "metrics")), allow(dead_code))]
+    ok: bool,
+    notify: bool,
+    callbacks: Vec<Callback>,
+    watchers: Vec<Arc<Watch>>,
+    tees: Vec<Arc<Waypoints>>,
+    run_at: Vec<RunAt>,
+}
+
+impl Passage {
+    // wait out the head start of the waypoint
+    fn sleep(&self, w: &Waypoints) {
+        #[cfg(feature = "log")]
+        if let Some(t) = self.target_time_this.filter(|&t| t > self.now) {
+            log::trace!(
+                "waypoint {} sleeping {:?} for head start",
+                self.this,
+                t - self.now
+            );
+        }
+        sleep_until(self.target_time_this, self.now, w.sleeper());
+    }
+
+    // wake the threads waiting for the next waypoint and call the callbacks of the passage
+    fn finish(&mut self, w: &Waypoints) {
+        #[cfg(feature = "log")]
+        if self.ok {
+            log::trace!("passed waypoint {} (current {})", self.this, self.next);
+        }
+        if self.notify {
+            w.cv.notify_all();
+        }
+        self.watchers.iter().for_each(|watch| watch.signal());
+        #[cfg(feature = "metrics")]
+        record_metrics(self.ok, self.wait, self.next);
+        self.tees.iter().for_each(|tee| {
+            // a strict tee that was advanced independently may refuse the gap
+            let _ = tee.skip_to(self.next);
+        });
+        self.callbacks.iter().for_each(|cb| cb());
+        std::mem::take(&mut self.run_at)
+            .into_iter()
+            .for_each(|r| (r.f)());
+    }
+}
+
+// a closure called once the current number reaches `n`
+struct RunAt {
+    n: usize,
+    f: Box<dyn FnOnce() + Send>,
+}
+
+impl fmt::Debug for RunAt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RunAt").field("n", &self.n).finish()
+    }
+}
+
+// the arguments for passing a waypoint with `Waypoints::advance`
+#[derive(Debug, Clone)]
+struct Pass {
+    rng: Range<usize>,
+    head_start: Option<Duration>,
+    // stop waiting once the deadline passes
+    deadline: Option<Instant>,
+    // accept a waypoint that was already passed, without passing it again
+    weak: bool,
+    // advance past the threads parked in the same band
+    parked: bool,
+    // wait until the gate is open as well
+    gate: Option<Arc<AtomicBool>>,
+    // let the threads waiting in the same band with a lower id pass first
+    id: Option<u32>,
+    // fail unless the sequence is in this epoch
+    epoch: Option<u64>,
+    // the deadline ends a slice of a longer wait, so `Builder::on_timeout` does not apply
+    poll: bool,
+}
+
+// the conditions besides the current number that a thread waits for in `Waypoints::wait_for`
+#[derive(Debug, Default, Clone, Copy)]
+struct Cond<'a> {
+    // wait until the gate is open
+    gate: Option<&'a AtomicBool>,
+    // wait until no thread with a lower rank is ready
+    rank: Option<Rank>,
+    // stop waiting once the sequence leaves the epoch
+    epoch: Option<u64>,
+}
+
+// the position of a thread waiting in `Waypoints::range_by`
+#[derive(Debug, Clone, Copy)]
+struct Rank {
+    band: (usize, usize),
+    key: (u32, u64),
+}
+
+impl Pass {
+    fn new(rng: Range<usize>, head_start: impl Into<Option<Duration>>) -> Self {
+        Self {
+            rng,
+            head_start: head_start.into(),
+            deadline: None,
+            weak: false,
+            parked: false,
+            gate: None,
+            id: None,
+            epoch: None,
+            poll: false,
+        }
+    }
+}
+
+impl State {
+    // mark the phases containing the passed waypoint `n` as entered, returning their callbacks if
+    // they were not entered before
+    fn enter_phases(&mut self, n: usize) -> Vec<Callback> {
+        let mut callbacks = Vec::new();
+        for phase in &mut self.named_phases {
+            if !phase.entered && phase.rng.contains(&n) {
+                phase.entered = true;
+                callbacks.extend(phase.on_enter.iter().cloned());
+            }
+        }
+        callbacks
+    }
+
+    // remove the closures of `Waypoints::run_at` that the current number reached
+    fn take_run_at(&mut self) -> Vec<RunAt> {
+        let n = self.n;
+        let (reached, pending) = std::mem::take(&mut self.run_at)
+            .into_iter()
+            .partition(|r| r.n <= n);
+        self.run_at = pending;
+        reached
+    }
+
+    // the error of `Waypoints::point_epoch` if the sequence is not in the `expected` epoch
+    fn check_epoch(&self, expected: Option<u64>) -> Option<ErrorKind> {
+        match expected {
+            Some(expected) if expected != self.epoch => Some(ErrorKind::StaleEpoch {
+                expected,
+                current: self.epoch,
+            }),
+            _ => None,
+        }
+    }
+
+    // a random delay of up to the maximum jitter
+    #[cfg(feature = "jitter")]
+    fn next_jitter(&mut self) -> Duration {
+        let (max, x) = match &mut self.jitter {
+            Some((max, x)) => (*max, x),
+            None => return Duration::ZERO,
+        };
+        // xorshift64
+        *x ^= *x << 13;
+        *x ^= *x >> 7;
+        *x ^= *x << 17;
+        max.mul_f64((*x >> 11) as f64 / (1u64 << 53) as f64)
+    }
+
+    // count an advance of the current number by `step` waypoints, `passes` of which were passed
+    // rather than skipped
+    fn count_advance(&mut self, step: usize, passes: usize) {
+        self.total_advances += step as u64;
+        self.passes += passes;
+    }
+
+    // delay the next waypoint by the paces of `Waypoints::pace_after` that the current number
+    // reached at `now`
+    fn apply_paces(&mut self, now: Instant) {
+        let n = self.n;
+        let (reached, pending) = std::mem::take(&mut self.paces)
+            .into_iter()
+            .partition::<Vec<_>, _>(|&(m, _)| m <= n);
+        self.paces = pending;
+        for (_, dt) in reached {
+            let t = now + dt;
+            self.target_time = Some(self.target_time.map_or(t, |t0| std::cmp::max(t0, t)));
+        }
+    }
+
+    fn register_waiter(&mut self, l: usize) -> u64 {
+        let ticket = self.next_ticket;
+        self.next_ticket += 1;
+        self.waiters.insert(ticket, l);
+        ticket
+    }
+
+    fn park(&mut self, band: (usize, usize)) -> u64 {
+        let ticket = self.next_ticket;
+        self.next_ticket += 1;
+        self.bands.entry(band).or_default().insert(ticket);
+        ticket
+    }
+
+    fn rank(&mut self, band: (usize, usize), id: u32) -> Rank {
+        let key = (id, self.next_ticket);
+        self.next_ticket += 1;
+        self.ranked.entry(band).or_default().insert(key);
+        Rank { band, key }
+    }
+
+    // remove a ranked thread from its band, returning whether other threads remain in it
+    fn unrank(&mut self, rank: Rank) -> bool {
+        let ranked = match self.ranked.get_mut(&rank.band) {
+            Some(ranked) => ranked,
+            None => return false,
+        };
+        ranked.remove(&rank.key);
+        if ranked.is_empty() {
+            self.ranked.remove(&rank.band);
+            return false;
+        }
+        true
+    }
+
+    // whether a ranked thread has to let a thread with a lower id pass first
+    fn yields(&self, rank: Rank) -> bool {
+        let first = self.ranked.get(&rank.band).and_then(|r| r.iter().next());
+        self.n < rank.band.1 && first.is_some_and(|&key| key != rank.key)
+    }
+
+    // remove a parked thread from its band, returning whether another thread passed its waypoint
+    fn unpark(&mut self, band: (usize, usize), ticket: u64) -> bool {
+        if self.released.remove(&ticket) {
+            return true;
+        }
+        if let Some(parked) = self.bands.get_mut(&band) {
+            parked.remove(&ticket);
+            if parked.is_empty() {
+                self.bands.remove(&band);
+            }
+        }
+        false
+    }
+
+    // release up to `count` threads parked in a band in arrival order, returning how many were
+    // released
+    fn release(&mut self, band: (usize, usize), count: usize) -> usize {
+        let parked = match self.bands.get_mut(&band) {
+            Some(parked) => parked,
+            None => return 0,
+        };
+        let tickets: Vec<_> = parked.iter().copied().take(count).collect();
+        tickets.iter().for_each(|t| {
+            parked.remove(t);
+        });
+        if parked.is_empty() {
+            self.bands.remove(&band);
+        }
+        self.released.extend(&tickets);
+        tickets.len()
+    }
+
+    // whether any waiting thread waits for a waypoint up to `n` or for any change
+    fn should_notify(&mut self, n: usize) -> bool {
+        let notify = self.generation_waiters > 0 || self.waiters.values().any(|&l| l <= n);
+        if notify {
+            self.stats.notifications += 1;
+        } else {
+            self.stats.skipped_notifications += 1;
+        }
+        notify
+    }
+
+    fn resolve(&self, mut n: usize) -> usize {
+        // follow chained aliases, stopping before an alias cycle repeats
+        let mut visited = vec![n];
+        while let Some(&m) = self.aliases.get(&n) {
+            if visited.contains(&m) {
+                break;
+            }
+            visited.push(m);
+            n = m;
+        }
+        let mut n = self.order.get(&n).copied().unwrap_or(n);
+        // shift past the waypoints inserted at or before the position
+        for &p in &self.insertions {
+            if n >= p {
+                n += 1;
+            }
+        }
+        n
+    }
+}
 
 /// Represents a series of waypoints.
 ///
 /// The struct has an internal state containing the next expected waypoint and the earliest time at
 /// which it may be passed.  All its methods are accessible via shared references, so typical usage
 /// would wrap [`Waypoints`] in an [`Arc`] to make it accessible from different threads.  The
-/// function [`Waypoints::new_arc`] creates an `Arc<Waypoints>>`.
+/// function [`Waypoints::new_arc`] creates an `Arc<Waypoints>>`.  Threads created with
+/// [`std::thread::scope`] can share a plain `&Waypoints` instead (see the crate documentation).
 #[derive(Debug)]
 pub struct Waypoints {
-    // tuple element 0: the current waypoint
-    // tuple element 1: the earliest time at which the next waypoint may be passed
-    state: Mutex<(usize, Option<Instant>)>,
+    state: Mutex<State>,
     cv: Condvar,
+    // a copy of the current waypoint number that can be read without locking
+    current: AtomicUsize,
+    // the time at which the `Waypoints` were created
+    start: Instant,
+    config: Config,
 }
 
 impl Waypoints {
     /// Create `Waypoints`.
     pub fn new() -> Self {
-        Self {
-            state: Mutex::new((0, None)),
-            cv: Condvar::new(),
-        }
+        Self::from_parts(State::default(), Config::default())
     }
 
     /// Create `Waypoints` wrapped in an [`Arc`].
@@ -109,11 +676,72 @@ impl Waypoints {
         Arc::new(Self::new())
     }
 
+    /// Create `Waypoints` with space reserved for recording the events of `n` waypoints.  See
+    /// [`Builder::capacity`].
+    pub fn with_capacity(n: usize) -> Self {
+        Builder::new().capacity(n).build()
+    }
+
+    /// Create a [`Builder`] to configure `Waypoints`.
+    pub fn builder() -> Builder {
+        Builder::new()
+    }
+
+    fn from_parts(state: State, config: Config) -> Self {
+        Self {
+            current: AtomicUsize::new(state.n),
+            state: Mutex::new(state),
+            cv: Condvar::new(),
+            start: Instant::now(),
+            config,
+        }
+    }
+
+    /// Create `Waypoints` that reproduce the timing of a log recorded by [`Self::events`].  In
+    /// addition to enforcing the order, each waypoint in the log may not pass sooner after its
+    /// predecessor than it did when it was recorded, as if the predecessor had been passed with
+    /// a corresponding `head_start`.
+    pub fn replay(log: Vec<EventRecord>) -> Self {
+        let w = Self::new();
+        w.state_lck().gaps = record::gaps(log);
+        w
+    }
+
+    /// Create `Waypoints` that pass waypoints in the order given by the `WAYPOINTS_ORDER`
+    /// environment variable, a comma-separated permutation of `0..k` such as `0,2,1,3`.  In that
+    /// example [`Self::point`] passes waypoint 2 before waypoint 1, i.e. `point(1)` waits until
+    /// `point(2)` was passed.  Waypoints from `k` onward keep their usual order, and the order
+    /// applies after resolving aliases (see [`Self::alias`]); [`Self::range`] is not affected.  If
+    /// the variable is not set, this is the same as [`Self::new`].
+    ///
+    /// A thread that calls `point` before its turn in the prescribed order waits for its turn
+    /// rather than returning an error, the same as a thread that arrives early at a waypoint of
+    /// the usual order: early arrival is how threads are synchronized, so it cannot be told apart
+    /// from a violation of the order.  An order that the test cannot follow (e.g. one requiring a
+    /// waypoint before another of the same thread) therefore stalls instead of failing; use
+    /// [`Self::with_timeout`] or [`Builder::max_wait`] to turn the stall into an
+    /// [`ErrorKind::TimedOut`], whose `expected` field is the position in the order that was not
+    /// reached.  A thread that arrives after its turn was passed returns
+    /// [`ErrorKind::AlreadyPassed`] as usual.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `WAYPOINTS_ORDER` is not a permutation of `0..k`.
+    #[cfg(feature = "env-order")]
+    pub fn from_env_order() -> Self {
+        let w = Self::new();
+        if let Ok(order) = std::env::var("WAYPOINTS_ORDER") {
+            w.state_lck().order = parse_order(&order)
+                .unwrap_or_else(|err| panic!("invalid WAYPOINTS_ORDER {:?}: {}", order, err));
+        }
+        w
+    }
+
     fn state_lck(&self) -> Guard<'_> {
         Self::into_guard(self.state.lock())
     }
 
-    fn into_guard(state: LockResult<Guard<'_>>) -> Guard<'_> {
+    fn into_guard<T>(state: LockResult<T>) -> T {
         // the data held by the guard should not be corrupted (none of the operations performed
         // while the lock is held should panic), so `Err` variant should be ok to use
         match state {
@@ -122,165 +750,3169 @@ impl Waypoints {
         }
     }
 
-    /// Reset the `Waypoints` to start at point 0 without an time requirement.
+    /// Reset the `Waypoints` to start at point 0 without an time requirement, keeping the recorded
+    /// events and counters; use [`Self::reset_full`] to clear those too, e.g. before reusing the
+    /// `Waypoints` in another test case.  This also revokes a [`Self::cancel`] and starts a new
+    /// [`Self::epoch`].  Like any move of the current number, it forgets the threads of
+    /// [`Self::point_reentrant`] and the previous step of [`Builder::head_start_ratio`].
+    /// Everything else is kept, notably the recorded events and the counters that accumulate across
+    /// cycles ([`Self::stats`], [`Self::wait_histogram`], [`Self::peak_waiters`],
+    /// [`Self::total_advances`] and [`Self::passed`]).
     pub fn reset(&self) {
-        self.set(0, None);
+        let mut state_lck = self.state_lck();
+        state_lck.cancelled = false;
+        state_lck.epoch += 1;
+        self.store(state_lck, 0, None);
     }
 
-    /// Set the `Waypoints` to a particular state.  Argument `t` is the time at which the next
-    /// waypoint may pass.
-    pub fn set(&self, n: usize, t: Option<Instant>) {
-        *self.state_lck() = (n, t);
+    /// Same as [`Self::reset`], but also return the records and counters to their initial state,
+    /// for reusing the `Waypoints` in another test case: this discards the recorded events (of
+    /// [`Self::events`] and [`Self::recent`]) and a pending [`Self::set_next_head_start`], clears
+    /// [`Self::stats`] (except for the notification of the reset itself),
+    /// [`Self::wait_histogram`], [`Self::total_advances`] and [`Self::passed`], and restarts
+    /// [`Self::peak_waiters`] from the threads waiting now.  Whether events are recorded is
+    /// kept, as are the [`Self::generation`] (which waiting threads compare against) and the
+    /// configuration of the sequence, such as aliases, gaps, phases and pending [`Self::run_at`]
+    /// closures.
+    pub fn reset_full(&self) {
+        let mut state_lck = self.state_lck();
+        state_lck.events.clear();
+        state_lck.recent.clear();
+        state_lck.next_head_start = None;
+        state_lck.stats = Stats::default();
+        state_lck.waits.clear();
+        state_lck.total_advances = 0;
+        state_lck.passes = 0;
+        state_lck.peak_waiters = state_lck.waiters.len();
+        state_lck.cancelled = false;
+        state_lck.epoch += 1;
+        self.store(state_lck, 0, None);
     }
 
-    /// Allow the waypoint to be passed if the current number matches exactly.  See
-    /// [`Self::range`] for the `head_start` argument.
-    pub fn point(&self, n: usize, head_start: Option<Duration>) -> Result<(), usize> {
-        self.range(n..n + 1, head_start)
+    /// Cancel the sequence: threads waiting for a waypoint return an [`ErrorKind::Cancelled`],
+    /// and so does every later call that passes waypoints (such as [`Self::point`],
+    /// [`Self::range`] or [`Self::own_run`]), immediately and without waiting or advancing.
+    /// Since the cancellation is checked under the lock, a thread that calls `point` just after
+    /// `cancel` cannot miss the notification and block forever.  Waiting for a change without
+    /// passing a waypoint (e.g. [`Self::wait_future`]) is not affected.  [`Self::reset`] revokes
+    /// the cancellation.
+    pub fn cancel(&self) {
+        let mut state_lck = self.state_lck();
+        state_lck.cancelled = true;
+        state_lck.stats.notifications += 1;
+        drop(state_lck);
+        self.cv.notify_all();
     }
 
-    /// Allow a waypoint to be passed if the current number is within the range (inclusive lower
-    /// bound, exclusive upper bound).  This can be used to have multiple threads pass a waypoint
-    /// concurrently rather than any particular thread being advantaged.  Argument `head_start`
-    /// represents the minimum amount of time between calling this method and the next waypoint
-    /// being allowed to pass.  The `Result` is an `Err` if a another waypoint previously use the
-    /// same waypoint number.
-    pub fn range(&self, mut rng: Range<usize>, head_start: Option<Duration>) -> Result<(), usize> {
-        let (res, mut state_lck) = if rng.is_empty() {
-            let state_lck = self.state_lck();
-            (Err(state_lck.0), state_lck)
-        } else {
-            let l = rng.next().expect("check rng is not empty");
-            let h = 1 + rng.last().unwrap_or(l);
-            let state_lck = self.cv.wait_while(self.state_lck(), |&mut (n, _)| n < l);
-            let state_lck = Self::into_guard(state_lck);
+    /// Whether the sequence was cancelled with [`Self::cancel`].
+    pub fn is_cancelled(&self) -> bool {
+        self.state_lck().cancelled
+    }
 
-            // check the state
-            let res = match *state_lck {
-                (n, _) if l <= n && n < h => Ok(()),
-                (n, _) if n >= h => Err(n),
-                _ => unreachable!("passed waypoint before schedule"),
-            };
-            (res, state_lck)
-        };
+    /// Move the current number forward to `n`, keeping the time at which the next waypoint may
+    /// pass.  Nothing changes if the current number is already at least `n`, so unlike
+    /// [`Self::set`] this never moves the sequence back.  The `Result` is an [`ErrorKind::Gap`]
+    /// if [`Builder::strict_sequence`] is enabled and `n` skips waypoints.
+    #[track_caller]
+    pub fn skip_to(&self, n: usize) -> Result<(), WaypointError> {
+        let mut state_lck = self.state_lck();
+        if state_lck.n >= n {
+            return Ok(());
+        }
+        if self.config.strict_sequence && n > state_lck.n + 1 {
+            return Err(ErrorKind::Gap {
+                from: state_lck.n,
+                to: n,
+            }
+            .into());
+        }
+        let step = n - state_lck.n;
+        state_lck.count_advance(step, 0);
+        let t = state_lck.target_time;
+        self.store(state_lck, n, t);
+        Ok(())
+    }
 
-        // update state
-        let (ref mut n, ref mut target_time) = *state_lck;
-        *n += 1;
-        let now = Instant::now();
-        let target_time_this = *target_time;
-        *target_time = match (*target_time, head_start) {
-            (Some(t), Some(dt)) => Some(std::cmp::max(now, t) + dt),
-            (Some(t), None) if now < t => Some(t),
-            (Some(_), None) => None,
-            (None, Some(dt)) => Some(now + dt),
-            (None, None) => None,
-        };
+    /// Create a [`WeakWaypoints`] handle that does not keep these `Waypoints` alive, e.g. for
+    /// detached threads that should not hang once the rest of the test is done.
+    pub fn downgrade(self: &Arc<Self>) -> WeakWaypoints {
+        WeakWaypoints::new(Arc::downgrade(self))
+    }
 
-        // drop lock before sleeping
-        drop(state_lck);
+    /// Spawn a thread that advances the current number by one for each tick received from `ticks`,
+    /// as [`Self::skip_to`] with the next number would, but atomically.  Threads waiting on
+    /// waypoints proceed as the ticks arrive, so the rate of progression is set by an external
+    /// source (a timer, a channel fed by a debugger script, ...) rather than by the threads
+    /// themselves.  The thread keeps the `Waypoints` alive until the sender is dropped, then
+    /// exits; the returned handle allows joining it.
+    pub fn pace_with(self: &Arc<Self>, ticks: Receiver<()>) -> JoinHandle<()> {
+        let w = self.clone();
+        std::thread::spawn(move || {
+            for () in ticks {
+                let mut state_lck = w.state_lck();
+                state_lck.count_advance(1, 1);
+                let (n, t) = (state_lck.n + 1, state_lck.target_time);
+                w.store(state_lck, n, t);
+            }
+        })
+    }
 
-        match target_time_this {
-            Some(t) if now < t => std::thread::sleep(t - now),
-            _ => {}
-        }
+    /// Mirror the passages of these `Waypoints` into `other`: after each waypoint is passed,
+    /// `other` is moved forward to the new current number with [`Self::skip_to`].  This lets an
+    /// observer sequence shadow this one, e.g. to wait on it in assertions, without the threads
+    /// passing the waypoints knowing about it.  `other` is updated after the state of these
+    /// `Waypoints` is unlocked, so a thread waiting on `other` may observe it slightly after the
+    /// waypoint was passed, but the updates arrive in order.  If `other` is also advanced
+    /// independently, the two sequences diverge: `other` is only ever moved forward, so it stays
+    /// ahead until this sequence catches up, and an [`ErrorKind::Gap`] of a strict `other` is
+    /// ignored.  A cycle of tees keeps the `Waypoints` involved alive.
+    pub fn tee(&self, other: Arc<Waypoints>) {
+        self.state_lck().tees.push(other);
+    }
 
-        self.cv.notify_all();
+    /// Reset the `Waypoints` to start at point 0, but keep the time at which the next waypoint may
+    /// pass.  Unlike [`Self::reset`], which drops the pending head start, this lets the pacing of
+    /// one phase of a test carry over into the next phase when its waypoints restart at 0.  Use
+    /// [`Self::set`] to choose both the number and the time.
+    pub fn reset_counter_keep_time(&self) {
+        let mut state_lck = self.state_lck();
+        state_lck.epoch += 1;
+        let t = state_lck.target_time;
+        self.store(state_lck, 0, t);
+    }
 
-        res
+    /// The number of times the `Waypoints` were reset with [`Self::reset`] or
+    /// [`Self::reset_counter_keep_time`], for passing to [`Self::point_epoch`].
+    pub fn epoch(&self) -> u64 {
+        self.state_lck().epoch
     }
-}
 
-impl Default for Waypoints {
-    fn default() -> Self {
-        Self::new()
+    /// Set the `Waypoints` to a particular state.  Argument `t` is the time at which the next
+    /// waypoint may pass.  Threads waiting on a waypoint are woken to re-check the new state, so a
+    /// thread waiting on a range that `n` has moved past returns an `Err`.  The current number
+    /// cannot be moved back, since that would resurrect waypoints that were already passed: the
+    /// `Result` is an [`ErrorKind::Backward`] if `n` is less than the current number (use
+    /// [`Self::set_force`] to move back on purpose), or an [`ErrorKind::Gap`] if
+    /// [`Builder::strict_sequence`] is enabled and `n` skips waypoints.  The state is not changed
+    /// in either case.
+    #[track_caller]
+    pub fn set(&self, n: usize, t: Option<Instant>) -> Result<(), WaypointError> {
+        let state_lck = self.state_lck();
+        if n < state_lck.n {
+            return Err(ErrorKind::Backward {
+                from: state_lck.n,
+                to: n,
+            }
+            .into());
+        }
+        self.set_checked(state_lck, n, t)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Same as [`Self::set`], but also allow moving the current number back, e.g. to replay part
+    /// of a sequence.  Threads waiting on a waypoint that was already passed keep waiting until it
+    /// is reached again.
+    #[track_caller]
+    pub fn set_force(&self, n: usize, t: Option<Instant>) -> Result<(), WaypointError> {
+        self.set_checked(self.state_lck(), n, t)
+    }
 
-    #[test]
-    fn error_on_duplicate_waypoint() {
-        let w = Waypoints::new();
-        w.point(0, None).unwrap();
-        assert!(w.point(0, None).is_err());
+    #[track_caller]
+    fn set_checked(
+        &self,
+        state_lck: Guard<'_>,
+        n: usize,
+        t: Option<Instant>,
+    ) -> Result<(), WaypointError> {
+        if self.config.strict_sequence && n > state_lck.n + 1 {
+            return Err(ErrorKind::Gap {
+                from: state_lck.n,
+                to: n,
+            }
+            .into());
+        }
+        self.store(state_lck, n, t);
+        Ok(())
     }
 
-    #[test]
-    fn sequence() {
-        let v_point = Arc::new(Mutex::new(Vec::new()));
-        let v_range = Arc::new(Mutex::new(Vec::new()));
-        let w = Waypoints::new_arc();
-        let mut threads = Vec::new();
+    fn store(&self, mut state_lck: Guard<'_>, n: usize, t: Option<Instant>) {
+        state_lck.n = n;
+        state_lck.generation += 1;
+        self.mirror(&state_lck);
+        state_lck.target_time = t;
+        state_lck.last_passed = None;
+        state_lck.reentrant.retain(|&m, _| m < n);
+        state_lck.apply_paces(Instant::now());
+        state_lck.stats.notifications += 1;
+        let watchers = state_lck.watchers.clone();
+        let run_at = state_lck.take_run_at();
+        drop(state_lck);
+        self.cv.notify_all();
+        watchers.iter().for_each(|w| w.signal());
+        run_at.into_iter().for_each(|r| (r.f)());
+    }
 
-        threads.push({
-            let v_point = v_point.clone();
-            let v_range = v_range.clone();
-            let w = w.clone();
-            std::thread::spawn(move || {
-                v_point.lock().unwrap().push(0);
-                w.point(0, None).unwrap();
-                w.point(3, None).unwrap();
-                v_point.lock().unwrap().push(3);
-                v_point.lock().unwrap().push(4);
-                v_point.lock().unwrap().push(5);
-                v_point.lock().unwrap().push(6);
-                w.point(4, None).unwrap();
-                w.point(7, None).unwrap();
-                v_point.lock().unwrap().push(9);
-                w.point(8, None).unwrap();
-                w.range(9..12, None).unwrap();
-                for _ in 0..3 {
-                    std::thread::yield_now();
-                    v_range.lock().unwrap().push(11);
-                }
-            })
-        });
+    /// The number of waypoints passed so far, e.g. to render the progress of a sequence with a
+    /// known number of waypoints.  Since waypoints start at 0, this is also the number of the next
+    /// waypoint.  The count only increases, unless it is moved back by [`Self::set_force`] or
+    /// [`Self::reset`].
+    pub fn passed_count(&self) -> usize {
+        self.state_lck().n
+    }
 
-        threads.push({
-            let v_point = v_point.clone();
-            let v_range = v_range.clone();
-            let w = w.clone();
-            std::thread::spawn(move || {
-                w.point(1, None).unwrap();
-                v_point.lock().unwrap().push(1);
-                v_point.lock().unwrap().push(2);
-                w.point(2, None).unwrap();
-                w.range(9..12, None).unwrap();
-                for _ in 0..3 {
-                    std::thread::yield_now();
-                    v_range.lock().unwrap().push(22);
-                }
-            })
-        });
+    /// The number of the next expected waypoint, without advancing it, e.g. to print where the
+    /// sequence sits while debugging.  This is the same number as [`Self::passed_count`]; see
+    /// [`Self::current_relaxed`] for reading it without locking.
+    pub fn current(&self) -> usize {
+        self.state_lck().n
+    }
 
-        threads.push({
-            let v_point = v_point.clone();
-            let v_range = v_range.clone();
-            std::thread::spawn(move || {
-                w.point(5, None).unwrap();
-                v_point.lock().unwrap().push(7);
-                v_point.lock().unwrap().push(8);
-                w.point(6, None).unwrap();
-                w.range(9..12, None).unwrap();
-                for _ in 0..3 {
-                    std::thread::yield_now();
-                    v_range.lock().unwrap().push(33);
-                }
-            })
-        });
+    /// The earliest time at which the next waypoint may be passed, or `None` if it may be passed
+    /// as soon as the sequence reaches it.
+    pub fn scheduled_time(&self) -> Option<Instant> {
+        self.state_lck().target_time
+    }
 
-        threads.into_iter().for_each(|t| {
-            t.join().ok();
-        });
+    /// Whether the sequence has passed all waypoints before `max`, i.e. whether the current number
+    /// is at least `max`.
+    pub fn is_complete(&self, max: usize) -> bool {
+        self.state_lck().n >= max
+    }
+
+    /// Declare that a participating thread will pass the waypoints `numbers`, for checking the
+    /// sequence with [`Self::validate`] before the threads start.
+    pub fn declare_participant(&self, numbers: impl IntoIterator<Item = usize>) {
+        self.state_lck().coverage.extend(numbers);
+    }
+
+    /// Check that the declared participants (see [`Self::declare_participant`]) cover every
+    /// waypoint up to the final one, i.e. up to [`Builder::expected_final`] if configured or else
+    /// up to the largest declared number.  The `Result` is an `Err` containing the missing numbers
+    /// in ascending order: no participant will pass them, so the threads waiting for later
+    /// waypoints would deadlock.  The numbers are resolved like those of [`Self::point`] (e.g.
+    /// with [`Self::alias`]), so this predicts the deadlock without starting any thread.
+    pub fn validate(&self) -> Result<(), Vec<usize>> {
+        let state_lck = self.state_lck();
+        let covered: HashSet<_> = state_lck
+            .coverage
+            .iter()
+            .map(|&n| state_lck.resolve(n))
+            .collect();
+        let max = match self.config.expected_final {
+            Some(max) => max,
+            None => covered.iter().max().map_or(0, |&n| n + 1),
+        };
+        let missing: Vec<_> = (0..max).filter(|n| !covered.contains(n)).collect();
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(missing)
+        }
+    }
+
+    /// The number of the next waypoint, read without locking.  The value is updated while the
+    /// state is locked but read with [`Ordering::Relaxed`], so it may be momentarily stale (e.g.
+    /// relative to the time at which the next waypoint may pass).  This is sufficient for
+    /// progress displays and watchdogs, which should not contend for the lock that synchronizes
+    /// the threads they observe.
+    pub fn current_relaxed(&self) -> usize {
+        self.current.load(Ordering::Relaxed)
+    }
+
+    // update the copy of the current waypoint number while the state is locked
+    fn mirror(&self, state: &State) {
+        self.current.store(state.n, Ordering::Relaxed);
+    }
+
+    /// The time elapsed since the `Waypoints` were created.  This provides a common origin for
+    /// timing checks across threads; it is not affected by [`Self::reset`] or [`Self::set`].
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// A counter that is incremented whenever the current number changes, i.e. whenever a
+    /// waypoint is passed or the state is set.  Together with [`Self::wait_generation`] this allows
+    /// building custom wait loops that only re-check their condition after an actual change
+    /// rather than after a spurious wakeup.
+    pub fn generation(&self) -> u64 {
+        self.state_lck().generation
+    }
+
+    /// The total number of waypoints the current number advanced by since the `Waypoints` were
+    /// created, across resets: each waypoint passed (e.g. with [`Self::point`], [`Self::range`], a
+    /// tick of [`Self::pace_with`] or [`wait_all`]) counts once, and [`Self::skip_to`] and
+    /// [`Self::own_run`] count the waypoints they skip or pass.  Moving the current number with
+    /// [`Self::set`] or [`Self::reset`] does not count.  Unlike the current number, this reveals
+    /// extra or missing steps of a sequence that is reset for every cycle.
+    pub fn total_advances(&self) -> u64 {
+        self.state_lck().total_advances
+    }
+
+    /// The number of successful passes since the `Waypoints` were created, across resets: each
+    /// call that passed its waypoint (e.g. [`Self::point`] returning `Ok`, or a thread of
+    /// [`Self::range_parked`] released by another one) counts once, as do a tick of
+    /// [`Self::pace_with`] and each sequence of [`wait_all`], and [`Self::own_run`] counts each
+    /// waypoint of its run.  Calls that return an `Err` do not count, even if they advanced the
+    /// current number, and neither do the waypoints skipped by [`Self::skip_to`].
+    pub fn passed(&self) -> usize {
+        self.state_lck().passes
+    }
+
+    /// Block until the current number is at least `total`, i.e. until waypoints `0..total` were
+    /// passed, or until the sequence is cancelled with [`Self::cancel`].  This lets a coordinating
+    /// thread that never passes a waypoint itself wait for the threads it does not own (e.g. to
+    /// orchestrate a shutdown) instead of sleeping.  The coordinator counts as a waiting thread,
+    /// e.g. in [`Self::waiters`].
+    ///
+    /// ```
+    /// use waypoints::Waypoints;
+    ///
+    /// let w = Waypoints::new();
+    /// std::thread::scope(|s| {
+    ///     for n in 0..4 {
+    ///         let w = &w;
+    ///         s.spawn(move || w.point(n, None).unwrap());
+    ///     }
+    ///     w.wait_completed(4);
+    ///     assert_eq!(w.passed(), 4);
+    /// });
+    /// ```
+    pub fn wait_completed(&self, total: usize) {
+        drop(self.wait_for(self.state_lck(), total, None, Cond::default()));
+    }
+
+    /// Block until [`Self::generation`] differs from `seen` and return the new generation.
+    ///
+    /// ```
+    /// use waypoints::Waypoints;
+    ///
+    /// let w = Waypoints::new();
+    /// let seen = w.generation();
+    /// w.point(0, None).unwrap();
+    /// assert_eq!(w.wait_generation(seen), seen + 1);
+    /// ```
+    pub fn wait_generation(&self, seen: u64) -> u64 {
+        let mut state_lck = self.state_lck();
+        state_lck.generation_waiters += 1;
+        let state_lck = self.cv.wait_while(state_lck, |st| st.generation == seen);
+        let mut state_lck = Self::into_guard(state_lck);
+        state_lck.generation_waiters -= 1;
+        state_lck.generation
+    }
+
+    /// Block until the current number is at most `n` and return it.  Passing waypoints only moves
+    /// the current number forward, so this waits for a rollback, i.e. for [`Self::set_force`] or
+    /// [`Self::reset`] moving the sequence back, and returns immediately if the current number
+    /// already is at most `n`.  Together with the forward waits of [`Self::point`] this lets a
+    /// thread synchronize on both the progress and the rollback of a sequence.
+    pub fn wait_for_at_most(&self, n: usize) -> usize {
+        let state_lck = self.cv.wait_while(self.state_lck(), |st| st.n > n);
+        Self::into_guard(state_lck).n
+    }
+
+    /// Report what [`Self::point`] would do for waypoint `n` right now, without changing the
+    /// state.  This lets a monitoring thread relate the sequence to a target without interfering
+    /// with it; the result may be outdated as soon as it is returned.
+    pub fn probe(&self, n: impl Waypoint) -> Probe {
+        let state_lck = self.state_lck();
+        let n = state_lck.resolve(n.ordinal());
+        match state_lck.n {
+            current if current == n => Probe::WouldPass,
+            current if current < n => Probe::WouldBlock {
+                behind_by: n - current,
+            },
+            current => Probe::AlreadyPassed { current },
+        }
+    }
+
+    /// Whether the next waypoint is held back by the clock rather than by the order, i.e. whether
+    /// the time at which it may pass (set by a head start) is still in the future.  A thread
+    /// passing the next waypoint now would sleep until then instead of waiting for another
+    /// thread.  This tells a slow test that waits for a head start, which is expected, apart from
+    /// one that waits for a thread, which may be a bug.
+    pub fn is_time_gated(&self) -> bool {
+        let now = Instant::now();
+        self.state_lck().target_time.is_some_and(|t| t > now)
+    }
+
+    /// The number of threads currently waiting to pass a waypoint.
+    pub fn waiters(&self) -> usize {
+        self.state_lck().waiters.len()
+    }
+
+    /// The maximum number of threads that were waiting to pass a waypoint at the same time, since
+    /// the `Waypoints` were created or [`Self::reset_peak`] was called.  This allows asserting
+    /// that the intended number of threads blocked together, or bounding the contention.
+    pub fn peak_waiters(&self) -> usize {
+        self.state_lck().peak_waiters
+    }
+
+    /// Restart tracking [`Self::peak_waiters`] from the number of threads waiting now.
+    pub fn reset_peak(&self) {
+        let mut state_lck = self.state_lck();
+        state_lck.peak_waiters = state_lck.waiters.len();
+    }
+
+    /// The waypoint numbers that the waiting threads wait for, in the order in which the threads
+    /// started waiting.  This is meant for asserting the order in which contending threads are
+    /// served, and is only available with the `test-internals` feature, since the bookkeeping of
+    /// waiting threads is not part of the stable API.
+    #[cfg(any(test, feature = "test-internals"))]
+    pub fn waiter_order(&self) -> Vec<usize> {
+        self.state_lck().waiters.values().copied().collect()
+    }
+
+    /// Delay each thread by a random duration of up to `max` after it passes a waypoint, or stop
+    /// delaying threads with `None`.  The waypoints still force their order, but the code between
+    /// them runs with varying timing, which shakes out interleavings that the OS scheduler rarely
+    /// produces.  This is only available with the `jitter` feature.
+    #[cfg(feature = "jitter")]
+    pub fn set_jitter(&self, max: Option<Duration>) {
+        use std::collections::hash_map::RandomState;
+        use std::hash::{BuildHasher, Hasher};
+
+        let seed = RandomState::new().build_hasher().finish() | 1;
+        self.state_lck().jitter = max.map(|max| (max, seed));
+    }
+
+    /// Run `body` up to `attempts` times with randomized delays, and return whether `predicate`
+    /// held for the observations returned by any run.  Each run starts from waypoint 0 (see
+    /// [`Self::reset`]) and stops the attempts once the predicate holds.  The delays are those of
+    /// [`Self::set_jitter`], or up to 1ms if no jitter is set.  This proves that an interleaving
+    /// the waypoints do not force can happen, e.g. that an observation can come before another.
+    /// This is only available with the `jitter` feature.
+    ///
+    /// ```
+    /// use std::sync::Mutex;
+    /// use waypoints::Waypoints;
+    ///
+    /// let w = Waypoints::new();
+    /// let possible = w.fuzz_until(
+    ///     |w| {
+    ///         let obs = Mutex::new(Vec::new());
+    ///         std::thread::scope(|s| {
+    ///             s.spawn(|| {
+    ///                 w.point(0, None).unwrap();
+    ///                 obs.lock().unwrap().push('a');
+    ///             });
+    ///             s.spawn(|| {
+    ///                 w.point(1, None).unwrap();
+    ///                 obs.lock().unwrap().push('b');
+    ///             });
+    ///         });
+    ///         obs.into_inner().unwrap()
+    ///     },
+    ///     |obs| obs == ['b', 'a'],
+    ///     1000,
+    /// );
+    /// assert!(possible);
+    /// ```
+    #[cfg(feature = "jitter")]
+    pub fn fuzz_until<T>(
+        &self,
+        mut body: impl FnMut(&Self) -> Vec<T>,
+        predicate: impl Fn(&[T]) -> bool,
+        attempts: usize,
+    ) -> bool {
+        let jitter = self.state_lck().jitter;
+        if jitter.is_none() {
+            self.set_jitter(Some(Duration::from_millis(1)));
+        }
+        let possible = (0..attempts).any(|_| {
+            self.reset();
+            predicate(&body(self))
+        });
+        self.state_lck().jitter = jitter;
+        possible
+    }
+
+    /// Run `body` `runs` times and assert that every run produced the same observations and
+    /// passed the waypoints on the same threads (compared by thread name) as the first run.  Each
+    /// run starts from waypoint 0 (see [`Self::reset`]) and is recorded (see [`Self::record`]),
+    /// discarding previously recorded events.
+    /// This answers whether the waypoints are sufficient to pin the order of a test: if they are
+    /// not, the runs eventually differ.
+    ///
+    /// # Panics
+    ///
+    /// Panics with the first divergence if a run differs from the first run.
+    #[track_caller]
+    pub fn assert_deterministic<T: PartialEq + fmt::Debug>(
+        &self,
+        runs: usize,
+        mut body: impl FnMut(&Self) -> Vec<T>,
+    ) {
+        let recording = self.state_lck().recording;
+        let mut run = || {
+            self.reset();
+            let mut state_lck = self.state_lck();
+            state_lck.events.clear();
+            state_lck.recording = true;
+            drop(state_lck);
+            let obs = body(self);
+            let passages: Vec<_> = self
+                .events()
+                .into_iter()
+                .map(|e| (e.n, e.thread_name))
+                .collect();
+            (obs, passages)
+        };
+        let (first_obs, first_passages) = run();
+        for i in 1..runs {
+            let (obs, passages) = run();
+            if let Some(d) = first_divergence(&first_obs, &obs) {
+                panic!(
+                    "run {} diverged from run 0 at observation {}: {:?} != {:?}",
+                    i,
+                    d,
+                    obs.get(d),
+                    first_obs.get(d)
+                );
+            }
+            if let Some(d) = first_divergence(&first_passages, &passages) {
+                panic!(
+                    "run {} diverged from run 0 at passage {}: {:?} != {:?}",
+                    i,
+                    d,
+                    passages.get(d),
+                    first_passages.get(d)
+                );
+            }
+        }
+        self.state_lck().recording = recording;
+    }
+
+    /// Print a report of the state to stderr: the current waypoint, when the next waypoint may
+    /// pass, the [`Self::epoch`] and whether the sequence was cancelled (which tells a cancelled
+    /// sequence apart from a hang), and which waypoints the waiting threads wait for.  This is
+    /// meant to be called from a debugger or a watchdog thread when a test stops making progress.
+    pub fn dump(&self) {
+        eprint!("{}", self.report());
+    }
+
+    fn report(&self) -> String {
+        let mut report = String::new();
+        self.write_report(&mut report)
+            .expect("writing to a `String` does not fail");
+        report
+    }
+
+    fn write_report(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        let state_lck = self.state_lck();
+        match self.config.expected_final {
+            Some(max) => writeln!(f, "waypoints: current {} of {}", state_lck.n, max)?,
+            None => writeln!(f, "waypoints: current {}", state_lck.n)?,
+        }
+        let now = Instant::now();
+        match state_lck.target_time {
+            Some(t) if now < t => writeln!(f, "  next waypoint may pass in {:?}", t - now)?,
+            _ => writeln!(f, "  next waypoint may pass now")?,
+        }
+        let cancelled = if state_lck.cancelled {
+            "cancelled"
+        } else {
+            "not cancelled"
+        };
+        writeln!(f, "  epoch {}, {}", state_lck.epoch, cancelled)?;
+        writeln!(f, "  {} threads waiting", state_lck.waiters.len())?;
+        let mut awaited = BTreeMap::new();
+        for &l in state_lck.waiters.values() {
+            *awaited.entry(l).or_insert(0) += 1;
+        }
+        for (l, count) in awaited {
+            writeln!(f, "    {} waiting for {}", count, l)?;
+        }
+        if let Some(count) = self.config.phase_parties {
+            writeln!(
+                f,
+                "  phase {}: {} of {} threads arrived",
+                state_lck.phase, state_lck.phase_arrived, count
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Capture the state without blocking, or return `None` if another thread holds the lock at
+    /// the moment.  A watchdog that inspects the state periodically should use this rather than
+    /// locking, since waiting for the lock would contend with the threads it observes and perturb
+    /// their timing.  `None` is expected occasionally (e.g. while a thread passes a waypoint), so
+    /// the caller should retry on its next tick.
+    pub fn try_snapshot(&self) -> Option<WaypointsSnapshot> {
+        let state_lck = match self.state.try_lock() {
+            Ok(state_lck) => state_lck,
+            Err(TryLockError::Poisoned(err)) => err.into_inner(),
+            Err(TryLockError::WouldBlock) => return None,
+        };
+        Some(WaypointsSnapshot::new(&state_lck))
+    }
+
+    /// Counters describing how waiting threads were woken.  Passing a waypoint only wakes waiting
+    /// threads if one of them waits for a waypoint that was reached, so a thread passing many
+    /// consecutive waypoints does not repeatedly wake threads waiting for a later waypoint.
+    pub fn stats(&self) -> Stats {
+        self.state_lck().stats
+    }
+
+    /// The minimum, mean and maximum time the threads waited for each waypoint number, from
+    /// calling the method that passed it until passing it (including a head start), as
+    /// `(n, min, mean, max)` in ascending order of `n`.  The durations accumulate over all
+    /// passages of a number, e.g. over the cycles of a sequence that is [`Self::reset`]
+    /// repeatedly, so a wide spread points at the synchronization point that varies most.  This
+    /// is always collected, with std types only, in one running accumulator per waypoint number,
+    /// so it does not grow over repeated cycles; see the `metrics` feature for exporting waits.
+    pub fn wait_histogram(&self) -> Vec<(usize, Duration, Duration, Duration)> {
+        self.state_lck()
+            .waits
+            .iter()
+            .map(|(&n, t)| (n, t.min, t.total / t.count, t.max))
+            .collect()
+    }
+
+    /// Use `dt` instead of the `head_start` argument of the next waypoint that is passed.  This
+    /// allows widening the timing margin of a particular transition while a test is running (e.g.
+    /// from a watchdog thread), without changing the calls that pass waypoints.
+    pub fn set_next_head_start(&self, dt: Duration) {
+        self.state_lck().next_head_start = Some(dt);
+    }
+
+    /// Start (`enable` is `true`) or stop recording the waypoints that are passed.  Starting to
+    /// record discards previously recorded events.
+    pub fn record(&self, enable: bool) {
+        let mut state_lck = self.state_lck();
+        if enable && !state_lck.recording {
+            state_lck.events.clear();
+        }
+        state_lck.recording = enable;
+    }
+
+    /// The waypoints passed while recording, in the order in which they were passed.
+    pub fn events(&self) -> Vec<EventRecord> {
+        self.state_lck().events.clone()
+    }
+
+    /// Keep the last `capacity` passed waypoints, independently of [`Self::record`], so a long
+    /// running test can show what happened just before it stopped making progress without keeping
+    /// its entire history.  Once `capacity` events are kept, each passage silently discards the
+    /// oldest one.  Setting a new capacity discards the kept events; a capacity of 0 stops
+    /// keeping events.
+    pub fn record_last(&self, capacity: usize) {
+        let mut state_lck = self.state_lck();
+        state_lck.recent = VecDeque::with_capacity(capacity);
+        state_lck.recent_capacity = capacity;
+    }
+
+    /// The events kept since [`Self::record_last`] was called, oldest first.
+    pub fn recent(&self) -> Vec<EventRecord> {
+        self.state_lck().recent.iter().cloned().collect()
+    }
+
+    /// Write the events recorded while [`Self::record`] is enabled to the file at `path` as
+    /// Trace Event Format JSON, which can be opened in `chrome://tracing` or Perfetto.  Each
+    /// passage is a duration event spanning from the arrival of the thread at the waypoint to
+    /// passing it, in microseconds since the `Waypoints` were created.  Threads are labelled with
+    /// their names, or their ids for unnamed threads.
+    pub fn export_chrome_trace(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let trace = record::chrome_trace(&self.events(), self.start);
+        std::fs::write(path, trace)
+    }
+
+    /// Insert a new waypoint at position `n`, shifting the later waypoints up by one: from now on
+    /// [`Self::point`] (and its variants) treats waypoint `m` as `m + 1` if `m >= n`.  Since
+    /// [`Self::range`] addresses positions directly, the new waypoint is passed with
+    /// `range(n..n + 1, ..)`.  This allows adding a step to a sequence, e.g. while debugging,
+    /// without renumbering the existing waypoints.
+    ///
+    /// Waypoint numbers are resolved when a thread starts waiting, so this is only safe while no
+    /// thread is waiting on or passing a waypoint of these `Waypoints`, e.g. while the other
+    /// threads are stopped in a debugger.
+    pub fn insert_before(&self, n: usize) {
+        self.state_lck().insertions.push(n);
+    }
+
+    /// Make [`Self::point`] (and its variants) treat waypoint `from` as waypoint `to`, e.g. while
+    /// renumbering waypoints.  Aliases are resolved transitively, so aliasing 1 to 2 and 2 to 3
+    /// makes `point(1)` behave as `point(3)`; a cycle of aliases resolves to the last number before
+    /// the cycle repeats.  An aliased waypoint is the same waypoint as its target, so passing
+    /// both `from` and `to` returns an `Err` for whichever is passed second.  Aliasing a number to
+    /// itself removes its alias.  [`Self::range`] does not resolve aliases.
+    pub fn alias(&self, from: usize, to: usize) {
+        let mut state_lck = self.state_lck();
+        if from == to {
+            state_lck.aliases.remove(&from);
+        } else {
+            state_lck.aliases.insert(from, to);
+        }
+    }
+
+    /// Allow the waypoint to be passed if the current number matches exactly.  See
+    /// [`Self::range`] for the `head_start` argument.  A waypoint is passed once: calling `point`
+    /// with the same number again, even from the same thread (e.g. in recursive code), returns an
+    /// [`ErrorKind::AlreadyPassed`].  See [`Self::point_reentrant`] for passing a number once per
+    /// thread instead, and [`Self::first_come`] for numbers that any of several threads may pass.
+    #[track_caller]
+    pub fn point(
+        &self,
+        n: impl Waypoint,
+        head_start: impl Into<Option<Duration>>,
+    ) -> Result<(), WaypointError> {
+        let (n, first_come) = {
+            let state_lck = self.state_lck();
+            let n = state_lck.resolve(n.ordinal());
+            (n, state_lck.first_come.contains(&n))
+        };
+        if first_come {
+            return self.pass_first_come(n, head_start.into()).map(drop);
+        }
+        self.range(n..n + 1, head_start)
+    }
+
+    /// Declare waypoint `n` as first-come: whichever thread reaches it first passes it, and the
+    /// other threads that call [`Self::point`] with `n` skip it, returning `Ok` instead of an
+    /// [`ErrorKind::AlreadyPassed`].  This models one of several workers doing a step without
+    /// predetermining which.  A skipped thread returns as soon as the waypoint was passed, without
+    /// sleeping for the head start of the thread that passed it, and continues to its next
+    /// number, which it waits for as usual.  See [`Self::point_first_come`] for telling whether
+    /// a thread passed the waypoint or skipped it.
+    pub fn first_come(&self, n: usize) {
+        self.state_lck().first_come.insert(n);
+    }
+
+    /// Same as [`Self::point`] for a waypoint declared with [`Self::first_come`], but return
+    /// `Ok(true)` if this thread passed the waypoint and `Ok(false)` if another thread passed it
+    /// first.  A waypoint that was not declared as first-come is treated as first-come by this
+    /// call.
+    #[track_caller]
+    pub fn point_first_come(
+        &self,
+        n: impl Waypoint,
+        head_start: impl Into<Option<Duration>>,
+    ) -> Result<bool, WaypointError> {
+        let n = self.state_lck().resolve(n.ordinal());
+        self.pass_first_come(n, head_start.into())
+    }
+
+    #[track_caller]
+    fn pass_first_come(
+        &self,
+        n: usize,
+        head_start: Option<Duration>,
+    ) -> Result<bool, WaypointError> {
+        let mut passed = false;
+        let pass = Pass {
+            weak: true,
+            ..Pass::new(n..n + 1, head_start)
+        };
+        self.advance_with(pass, |_, _| passed = true)?;
+        Ok(passed)
+    }
+
+    /// Allow a waypoint to be passed if the current number is within the range (inclusive lower
+    /// bound, exclusive upper bound).  This can be used to have multiple threads pass a waypoint
+    /// concurrently rather than any particular thread being advantaged.  Argument `head_start`
+    /// represents the minimum amount of time between calling this method and the next waypoint
+    /// being allowed to pass; it can be given as a [`Duration`], `Some(Duration)` or `None`.  The
+    /// `Result` is an [`ErrorKind::AlreadyPassed`] if another waypoint previously used the same
+    /// waypoint number.  A range with a lower bound greater than its upper bound returns
+    /// [`ErrorKind::InvalidRange`] immediately without waiting or advancing.  See
+    /// [`Self::range_fifo`] for passing the waypoints in the order the threads arrived instead.
+    #[track_caller]
+    pub fn range(
+        &self,
+        rng: Range<usize>,
+        head_start: impl Into<Option<Duration>>,
+    ) -> Result<(), WaypointError> {
+        self.range_next(rng, head_start.into()).map(drop)
+    }
+
+    /// Same as [`Self::range`], but pass the waypoints of all threads parked within the range at
+    /// once.  Each call parks the thread in the band identified by `rng`.  The first parked thread
+    /// to find the current number `n` within the range becomes the leader: it releases up to
+    /// `h - n - 1` other threads parked in the band (where `h` is the exclusive upper bound), in
+    /// the order they arrived, and advances the current number by 1 plus the number of threads it
+    /// released.  Released threads return `Ok` without advancing further.  With
+    /// [`Builder::strict_sequence`] enabled no threads are released, so each thread passes only
+    /// its own waypoint.  Threads that were not
+    /// released because the band was full, or that arrive later, wait for the next leader and
+    /// return an `Err` under the same conditions as [`Self::range`].  Only the leader records an
+    /// event and applies `head_start`.
+    #[track_caller]
+    pub fn range_parked(
+        &self,
+        rng: Range<usize>,
+        head_start: impl Into<Option<Duration>>,
+    ) -> Result<(), WaypointError> {
+        self.advance(Pass {
+            parked: true,
+            ..Pass::new(rng, head_start)
+        })
+        .map(drop)
+    }
+
+    /// Same as [`Self::point`], but a thread may call this repeatedly with the same number: the
+    /// first call passes the waypoint, and further calls from the same thread return `Ok`
+    /// immediately without waiting or advancing.  The thread that passed each number is tracked
+    /// under the lock by its [`ThreadId`], so a different thread calling this with the same
+    /// number still returns an [`ErrorKind::AlreadyPassed`].  Moving the current number back to
+    /// or before the number (e.g. with [`Self::reset`]) forgets the thread, so the waypoint is
+    /// passed again.
+    #[track_caller]
+    pub fn point_reentrant(
+        &self,
+        n: impl Waypoint,
+        head_start: impl Into<Option<Duration>>,
+    ) -> Result<(), WaypointError> {
+        let id = std::thread::current().id();
+        let n = {
+            let state_lck = self.state_lck();
+            let n = state_lck.resolve(n.ordinal());
+            if state_lck.reentrant.get(&n) == Some(&id) {
+                return Ok(());
+            }
+            n
+        };
+        let pass = Pass::new(n..n + 1, head_start);
+        self.advance_with(pass, |state, _| {
+            state.reentrant.insert(n, id);
+        })
+        .map(drop)
+    }
+
+    /// Same as [`Self::range`], but pass the waypoints of threads that are ready at the same time
+    /// in ascending order of `id` rather than in the order the OS happens to schedule them.  A
+    /// thread whose waypoint is within the range lets the threads waiting in the same band (i.e.
+    /// with the same `rng`) with a lower `id` pass first, so the observable order is the same on
+    /// every run as long as the threads are waiting before the range is reached.  A thread that
+    /// arrives later is ordered among the threads still waiting.  Threads with the same `id` pass
+    /// in the order they arrived.
+    #[track_caller]
+    pub fn range_by(
+        &self,
+        rng: Range<usize>,
+        id: u32,
+        head_start: impl Into<Option<Duration>>,
+    ) -> Result<(), WaypointError> {
+        self.advance(Pass {
+            id: Some(id),
+            ..Pass::new(rng, head_start)
+        })
+        .map(drop)
+    }
+
+    /// Same as [`Self::range`], but pass the waypoints of threads that are ready at the same time
+    /// in the order the threads began waiting, so a thread that arrived earlier is released
+    /// first.  This reproduces a race in which the arrival order matters deterministically, as
+    /// long as the threads arrive in a known order (e.g. one after another is seen in
+    /// [`Self::waiters`]).  It is the same order as [`Self::range_by`] with the same `id` for
+    /// every thread, and threads of both methods in the same band are ordered as if these had
+    /// `id` 0.
+    #[track_caller]
+    pub fn range_fifo(
+        &self,
+        rng: Range<usize>,
+        head_start: impl Into<Option<Duration>>,
+    ) -> Result<(), WaypointError> {
+        self.range_by(rng, 0, head_start)
+    }
+
+    /// Same as [`Self::point`], but return the earliest time at which the next waypoint may be
+    /// passed, as scheduled by this call (including its `head_start`), or `None` if the next
+    /// waypoint may be passed immediately.  This allows logging the delay imposed on the next
+    /// waypoint, e.g. "next step gated until +80ms".
+    #[track_caller]
+    pub fn point_scheduled(
+        &self,
+        n: impl Waypoint,
+        head_start: impl Into<Option<Duration>>,
+    ) -> Result<Option<Instant>, WaypointError> {
+        let n = self.state_lck().resolve(n.ordinal());
+        self.advance_with(Pass::new(n..n + 1, head_start), |_, _| ())
+            .map(|(_, scheduled)| scheduled)
+    }
+
+    /// Same as [`Self::point`], but push `value` onto `sink` as part of passing the waypoint.  The
+    /// push happens while the state of the `Waypoints` is locked, so no other thread can pass the
+    /// next waypoint before the observation is recorded.  The value is dropped if the waypoint is
+    /// not passed.
+    ///
+    /// To avoid a deadlock, the `sink` is locked while the state is locked: no thread may wait on
+    /// these `Waypoints` while holding the lock of `sink`.
+    #[track_caller]
+    pub fn point_push<T>(
+        &self,
+        n: impl Waypoint,
+        head_start: impl Into<Option<Duration>>,
+        sink: &Mutex<Vec<T>>,
+        value: T,
+    ) -> Result<(), WaypointError> {
+        let n = self.state_lck().resolve(n.ordinal());
+        let push = |_: &mut State, _| Self::into_guard(sink.lock()).push(value);
+        self.advance_with(Pass::new(n..n + 1, head_start), push)
+            .map(drop)
+    }
+
+    /// Same as [`Self::point`], but call `f` with the new current number at the instant of the
+    /// advance, while the state is locked and before any other thread can observe the change.
+    /// This updates a closely coupled shared structure atomically with the waypoint, e.g. a flag
+    /// that a thread waiting for the next waypoint must see set.  `f` is not called if the
+    /// waypoint is not passed.
+    ///
+    /// `f` must be short and must not block: it must not use these `Waypoints` (which deadlocks,
+    /// since their state is locked), wait for other threads, or sleep, since every thread that
+    /// passes a waypoint or inspects the state is held up until it returns.  See
+    /// [`Self::point_push`] for recording an observation.  If `f` panics, the waypoint is still
+    /// passed and the waiting threads are notified before the panic propagates to the caller.
+    #[track_caller]
+    pub fn point_with_fn(
+        &self,
+        n: impl Waypoint,
+        head_start: impl Into<Option<Duration>>,
+        f: impl FnOnce(usize),
+    ) -> Result<(), WaypointError> {
+        let n = self.state_lck().resolve(n.ordinal());
+        self.advance_with(Pass::new(n..n + 1, head_start), |_, next| f(next))
+            .map(drop)
+    }
+
+    /// Pass the contiguous run of waypoints `a` through `b` (inclusive) at once.  The thread waits
+    /// for waypoint `a` once, then advances the current number to `b + 1` while holding the lock a
+    /// single time and notifies waiting threads a single time.  This is the lowest-overhead way to
+    /// hand off a long ordered segment, intended for setting up benchmarks rather than for
+    /// fine-grained interleaving tests: there is no head start, a pending head start is
+    /// discarded, and no events are recorded or phases entered for the run.  The `Result` is a
+    /// [`ErrorKind::AlreadyPassed`] if waypoint `a` was already passed, a
+    /// [`ErrorKind::InvalidRange`] if `a > b`, or an [`ErrorKind::Gap`] for a run of
+    /// several waypoints if [`Builder::strict_sequence`] is enabled; the run is not passed in
+    /// either case.
+    #[track_caller]
+    pub fn own_run(&self, a: usize, b: usize) -> Result<(), WaypointError> {
+        if a > b {
+            return Err(ErrorKind::InvalidRange { l: a, h: b + 1 }.into());
+        }
+        if self.config.strict_sequence && b > a {
+            return Err(ErrorKind::Gap { from: a, to: b + 1 }.into());
+        }
+        let (mut state_lck, _) = self.wait_for(self.state_lck(), a, None, Cond::default());
+        if state_lck.cancelled {
+            return Err(ErrorKind::Cancelled.into());
+        }
+        if state_lck.n != a {
+            return Err(ErrorKind::AlreadyPassed(state_lck.n).into());
+        }
+        let state = &mut *state_lck;
+        state.count_advance(b + 1 - a, b + 1 - a);
+        state.n = b + 1;
+        state.generation += 1;
+        self.mirror(state);
+        state.target_time = None;
+        state.apply_paces(Instant::now());
+        let notify = state.should_notify(state.n);
+        let watchers = state.watchers.clone();
+        let run_at = state.take_run_at();
+        drop(state_lck);
+
+        if notify {
+            self.cv.notify_all();
+        }
+        watchers.iter().for_each(|w| w.signal());
+        run_at.into_iter().for_each(|r| (r.f)());
+        Ok(())
+    }
+
+    /// Same as [`Self::point`], but also wait until `gate` is `true`, so the waypoint is passed
+    /// only once both the sequence has reached `n` and an external precondition holds.  Waiting
+    /// threads are not woken by storing to `gate` alone: the thread that opens the gate has to
+    /// call [`Self::notify_gate`] afterwards.
+    #[track_caller]
+    pub fn point_gated(
+        &self,
+        n: impl Waypoint,
+        gate: Arc<AtomicBool>,
+        head_start: impl Into<Option<Duration>>,
+    ) -> Result<(), WaypointError> {
+        let n = self.state_lck().resolve(n.ordinal());
+        self.advance(Pass {
+            gate: Some(gate),
+            ..Pass::new(n..n + 1, head_start)
+        })
+        .map(drop)
+    }
+
+    /// Wake the threads waiting in [`Self::point_gated`] to re-check their gates.  Call this after
+    /// storing `true` to a gate.
+    pub fn notify_gate(&self) {
+        let mut state_lck = self.state_lck();
+        state_lck.stats.notifications += 1;
+        drop(state_lck);
+        self.cv.notify_all();
+    }
+
+    /// Same as [`Self::point`], but only pass the waypoint in the epoch `epoch` (see
+    /// [`Self::epoch`]).  In long tests that reset and reuse a sequence, a slow thread might
+    /// otherwise pass a number of the next cycle that it meant for the previous one.  The
+    /// `Result` is an [`ErrorKind::StaleEpoch`] if the sequence is in a different epoch when the
+    /// call starts or is reset while the thread waits; the waypoint is not passed in either case.
+    #[track_caller]
+    pub fn point_epoch(
+        &self,
+        n: impl Waypoint,
+        epoch: u64,
+        head_start: impl Into<Option<Duration>>,
+    ) -> Result<(), WaypointError> {
+        let n = self.state_lck().resolve(n.ordinal());
+        self.advance(Pass {
+            epoch: Some(epoch),
+            ..Pass::new(n..n + 1, head_start)
+        })
+        .map(drop)
+    }
+
+    /// Same as [`Self::point`], but stop waiting once `deadline` passes.  The `Result` is a
+    /// [`ErrorKind::TimedOut`] if the waypoint was not reached in time, in which case the
+    /// waypoint is not passed.  The deadline bounds only the wait for the waypoint, not the sleep
+    /// imposed by a previous `head_start`.
+    #[track_caller]
+    pub fn point_deadline(
+        &self,
+        n: impl Waypoint,
+        head_start: impl Into<Option<Duration>>,
+        deadline: Instant,
+    ) -> Result<(), WaypointError> {
+        let n = self.state_lck().resolve(n.ordinal());
+        self.advance(Pass {
+            deadline: Some(deadline),
+            ..Pass::new(n..n + 1, head_start)
+        })
+        .map(drop)
+    }
+
+    /// Pass waypoint `n` only if that is possible without blocking: the `Result` is `Ok(true)` if
+    /// the current number was `n` and the waypoint was passed, `Ok(false)` if the sequence has not
+    /// reached `n` yet or the next waypoint is held back by a head start (see
+    /// [`Self::is_time_gated`]), and an [`ErrorKind::AlreadyPassed`] if `n` was already passed.
+    /// This is the non-blocking sibling of [`Self::point`], e.g. for polling loops.  Passing the
+    /// waypoint wakes the waiting threads as usual.
+    #[track_caller]
+    pub fn try_point(&self, n: impl Waypoint) -> Result<bool, WaypointError> {
+        let n = {
+            let state_lck = self.state_lck();
+            let n = state_lck.resolve(n.ordinal());
+            let now = Instant::now();
+            let gated = state_lck.target_time.is_some_and(|t| t > now);
+            match state_lck.n {
+                current if current > n => {
+                    return Err(ErrorKind::AlreadyPassed(current).into());
+                }
+                current if current < n || gated => return Ok(false),
+                _ => n,
+            }
+        };
+        let pass = Pass {
+            deadline: Some(Instant::now()),
+            poll: true,
+            ..Pass::new(n..n + 1, None)
+        };
+        match self.advance(pass) {
+            Ok(_) => Ok(true),
+            Err(err) if matches!(err.kind(), ErrorKind::TimedOut { .. }) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Same as [`Self::point_deadline`], but stop waiting once `wait_timeout` elapsed since the
+    /// call, so a test that never reaches the waypoint fails with an [`ErrorKind::TimedOut`]
+    /// instead of hanging; an [`ErrorKind::AlreadyPassed`] still means that another call passed
+    /// the waypoint.  The timeout bounds only the wait for the sequence to reach `n`: the sleep
+    /// for the head start of the previous waypoint starts once the waypoint is passed and may
+    /// take longer than `wait_timeout`, and the `head_start` of this call delays the next
+    /// waypoint as usual.
+    #[track_caller]
+    pub fn point_timeout(
+        &self,
+        n: impl Waypoint,
+        head_start: impl Into<Option<Duration>>,
+        wait_timeout: Duration,
+    ) -> Result<(), WaypointError> {
+        self.point_deadline(n, head_start, Instant::now() + wait_timeout)
+    }
+
+    /// Same as [`Self::point`], but treat a waypoint that was already passed as passed by this
+    /// call.  This is intended for sequences that are moved forward out of band with
+    /// [`Self::set`]: once the current number is at least `n`, the call returns `Ok` and only
+    /// advances the current number if it was exactly `n`.
+    #[track_caller]
+    pub fn point_weak(
+        &self,
+        n: impl Waypoint,
+        head_start: impl Into<Option<Duration>>,
+    ) -> Result<(), WaypointError> {
+        let n = self.state_lck().resolve(n.ordinal());
+        self.advance(Pass {
+            weak: true,
+            ..Pass::new(n..n + 1, head_start)
+        })
+        .map(drop)
+    }
+
+    /// Run `f`, limiting the wait of each waypoint of these `Waypoints` that `f` passes on the
+    /// current thread to `timeout`, as if it was passed with [`Self::point_deadline`].  A waypoint
+    /// that is not reached in time returns [`ErrorKind::TimedOut`].  This gives a whole test a
+    /// limit on how long it may block without changing each call.  The timeout is stored in a
+    /// thread-local, so it does not apply to other threads (including threads spawned by `f`), and
+    /// it does not override an explicit deadline.  Calls may be nested; the innermost timeout
+    /// applies.
+    pub fn with_timeout<R>(&self, timeout: Duration, f: impl FnOnce() -> R) -> R {
+        struct Reset;
+        impl Drop for Reset {
+            fn drop(&mut self) {
+                TIMEOUTS.with(|t| t.borrow_mut().pop());
+            }
+        }
+
+        TIMEOUTS.with(|t| t.borrow_mut().push((self.addr(), timeout)));
+        let _reset = Reset;
+        f()
+    }
+
+    fn sleeper(&self) -> &dyn Sleeper {
+        self.config.sleeper.as_deref().unwrap_or(&DefaultSleeper)
+    }
+
+    fn addr(&self) -> usize {
+        self as *const Self as usize
+    }
+
+    /// Define a phase named `name` that covers the waypoints in `rng`, e.g. to group a test into
+    /// setup, run and teardown phases.  Defining a phase with an existing name replaces its range.
+    pub fn define_phase(&self, name: &str, rng: Range<usize>) {
+        let mut state_lck = self.state_lck();
+        match state_lck.named_phases.iter_mut().find(|p| p.name == name) {
+            Some(phase) => phase.rng = rng,
+            None => state_lck.named_phases.push(NamedPhase {
+                name: name.to_string(),
+                rng,
+                entered: false,
+                on_enter: Vec::new(),
+            }),
+        }
+    }
+
+    /// Register `cb` to be called when a waypoint in the range of the phase named `name` (see
+    /// [`Self::define_phase`]) is passed for the first time.  The callback is called by the thread
+    /// that passed the waypoint after it released the internal lock, so it may use the
+    /// `Waypoints`.  Moving the current number with [`Self::set`] does not enter phases.  The
+    /// callback has to be `Send` and `Sync`, since several threads may call it:
+    ///
+    /// ```compile_fail
+    /// use std::cell::Cell;
+    /// use std::sync::Arc;
+    /// use waypoints::Waypoints;
+    ///
+    /// let w = Waypoints::new();
+    /// w.define_phase("setup", 0..2);
+    /// let calls = Arc::new(Cell::new(0));
+    /// w.on_phase_enter("setup", move || calls.set(calls.get() + 1));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if no phase named `name` was defined.
+    #[track_caller]
+    pub fn on_phase_enter(&self, name: &str, cb: impl Fn() + Send + Sync + 'static) {
+        let mut state_lck = self.state_lck();
+        let phase = state_lck
+            .named_phases
+            .iter_mut()
+            .find(|p| p.name == name)
+            .unwrap_or_else(|| panic!("no phase named {:?} was defined", name));
+        phase.on_enter.push(Arc::new(cb));
+    }
+
+    /// Delay waypoint `n` until `dt` after the current number reaches `n`, regardless of which
+    /// thread advances it there or what head start that thread passes.  This imposes a
+    /// pause at a point of the sequence ("once waypoint 5 is reached, wait 100ms") without tying
+    /// it to the call site of any waypoint.  If the current number already is at least `n`, the
+    /// next waypoint is delayed until `dt` from now.  A longer head start of the advancing thread
+    /// still applies.
+    pub fn pace_after(&self, n: usize, dt: Duration) {
+        let mut state_lck = self.state_lck();
+        state_lck.paces.push((n, dt));
+        if state_lck.n >= n {
+            state_lck.apply_paces(Instant::now());
+        }
+    }
+
+    /// Call `f` once, as soon as the current number reaches `n`, regardless of which thread
+    /// advances it there.  The closure is called by the thread that advanced the current number,
+    /// after the state is unlocked, so it may use these `Waypoints`; threads waiting for `n` may
+    /// proceed before the closure returns.  If the current number already is at least `n`, `f` is
+    /// called immediately by the current thread.  This anchors one-time setup to a point in the
+    /// sequence.
+    ///
+    /// Since any thread may call it, the closure has to be `Send`; a closure that is not is
+    /// rejected rather than making the `Waypoints` lose `Sync`:
+    ///
+    /// ```compile_fail
+    /// use std::rc::Rc;
+    /// use waypoints::Waypoints;
+    ///
+    /// let w = Waypoints::new();
+    /// let rc = Rc::new(0);
+    /// w.run_at(1, move || drop(rc));
+    /// ```
+    pub fn run_at(&self, n: usize, f: impl FnOnce() + Send + 'static) {
+        let mut state_lck = self.state_lck();
+        if state_lck.n >= n {
+            drop(state_lck);
+            f();
+        } else {
+            state_lck.run_at.push(RunAt { n, f: Box::new(f) });
+        }
+    }
+
+    /// Create a future that resolves once the current number is at least `n`, i.e. once
+    /// waypoint `n` may be passed, without passing it.  This allows async code to await a
+    /// waypoint reached by other threads.  See [`WaitFuture`] for how the future is woken.
+    pub fn wait_future(self: &Arc<Self>, n: usize) -> WaitFuture {
+        WaitFuture::new(self.clone(), n)
+    }
+
+    /// Create a [`ThreadSequence`] that passes `numbers` in order, for a thread that passes
+    /// several waypoints.  See also the [`wp_thread!`] macro.
+    pub fn thread_sequence(&self, numbers: Vec<usize>) -> ThreadSequence<'_> {
+        ThreadSequence::new(self, numbers)
+    }
+
+    /// Create a [`Turns`] for thread `thread` of `threads` threads that take turns, numbering
+    /// their waypoints automatically.  See also the [`wp_thread!`] macro.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `thread` is not less than `threads`.
+    pub fn turns(&self, thread: usize, threads: usize) -> Turns<'_> {
+        Turns::new(self, thread, threads)
+    }
+
+    /// Block until `count` threads have reached waypoint `n`, i.e. until waypoints `n..n + count`
+    /// have all been passed.  The first `count` callers pass the range concurrently (see
+    /// [`Self::range`]), then wait for each other before returning.  Returns `Ok(true)` for the
+    /// thread that completed the barrier, similar to [`std::sync::BarrierWaitResult::is_leader`].
+    /// The `Result` is an `Err` under the same conditions as [`Self::range`], including when
+    /// `count` is 0.
+    #[track_caller]
+    pub fn barrier(&self, n: usize, count: usize) -> Result<bool, WaypointError> {
+        let h = n + count;
+        let next = self.range_next(n..h, None)?;
+        drop(self.wait_for(self.state_lck(), h, None, Cond::default()));
+        Ok(next == h)
+    }
+
+    /// Wait until all threads of the current phase have arrived, then proceed to the next phase
+    /// together.  This acts as a reusable barrier for worker threads that pass the same phases
+    /// without being assigned distinct waypoint numbers.  The number of threads is configured
+    /// with [`Builder::phase_parties`]; if fewer threads ever arrive, the ones that did block
+    /// indefinitely.  Phases are counted independently of the waypoint numbers.  Returns the
+    /// phase that was completed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of threads was not configured.
+    #[track_caller]
+    pub fn point_phase(&self) -> usize {
+        let count = self
+            .config
+            .phase_parties
+            .expect("phase parties are configured with `Builder::phase_parties`");
+        let mut state_lck = self.state_lck();
+        let phase = state_lck.phase;
+        state_lck.phase_arrived += 1;
+        if state_lck.phase_arrived >= count {
+            state_lck.phase += 1;
+            state_lck.phase_arrived = 0;
+            drop(state_lck);
+            self.cv.notify_all();
+        } else {
+            let state_lck = self.cv.wait_while(state_lck, |st| st.phase == phase);
+            drop(Self::into_guard(state_lck));
+        }
+        phase
+    }
+
+    /// Return a guard that passes waypoint `n` with [`Self::point`] when it is dropped, ignoring
+    /// an `Err` if the waypoint was already passed.  Unlike the other methods, nothing waits
+    /// when the guard is created; dropping the guard blocks until waypoint `n` is reached.  This
+    /// ties the order of cleanup (e.g. at the end of a scope) into the sequence.
+    pub fn advance_on_drop(&self, n: usize) -> AdvanceOnDrop<'_> {
+        AdvanceOnDrop::new(self, n)
+    }
+
+    /// Return a guard that checks the sequence reached waypoint `n` (i.e. [`Self::is_complete`]
+    /// with `n`) when it is dropped, and otherwise panics with `waypoint sequence stalled at
+    /// current, expected n`.  This turns a thread that returned or panicked before passing its
+    /// waypoints into a failure of the test instead of a silent under-execution.  Unlike
+    /// [`Builder::expected_final`], the check happens at the end of the scope of the guard rather
+    /// than when the `Waypoints` are dropped, which may be much later for shared `Waypoints`.  The
+    /// check is skipped if the dropping thread is already panicking, so the original panic is not
+    /// masked.
+    ///
+    /// ```should_panic
+    /// use waypoints::Waypoints;
+    ///
+    /// let w = Waypoints::new();
+    /// let _guard = w.expect_all(2);
+    /// w.point(0, None).unwrap();
+    /// // panics with "waypoint sequence stalled at 1, expected 2"
+    /// ```
+    pub fn expect_all(&self, n: usize) -> ExpectAll<'_> {
+        ExpectAll::new(self, n)
+    }
+
+    /// Create a [`Barrier`] that calls [`Self::barrier`] for waypoint `n` with `count` threads.
+    /// This eases replacing an existing [`std::sync::Barrier`] with a waypoint.
+    pub fn as_barrier(&self, n: usize, count: usize) -> Barrier<'_> {
+        Barrier::new(self, n, count)
+    }
+
+    /// Create a [`CyclicBarrier`] that synchronizes `count` threads for `cycles` rounds starting
+    /// at waypoint `n`, and lets them pass without waiting afterwards.  The rounds use the
+    /// waypoints `n..n + cycles * count`.
+    pub fn cyclic_barrier(&self, n: usize, count: usize, cycles: usize) -> CyclicBarrier<'_> {
+        CyclicBarrier::new(self, n, count, cycles)
+    }
+
+    // pass a waypoint within `rng` and return the number of the next waypoint
+    #[track_caller]
+    fn range_next(
+        &self,
+        rng: Range<usize>,
+        head_start: Option<Duration>,
+    ) -> Result<usize, WaypointError> {
+        self.advance(Pass::new(rng, head_start))
+    }
+
+    #[track_caller]
+    fn advance(&self, pass: Pass) -> Result<usize, WaypointError> {
+        self.advance_with(pass, |_, _| ()).map(|(next, _)| next)
+    }
+
+    // same as `advance`, but call `on_pass` with the locked state and the next waypoint number if
+    // the waypoint is passed, and also return the earliest time at which the next waypoint may be
+    // passed
+    #[track_caller]
+    fn advance_with(
+        &self,
+        pass: Pass,
+        on_pass: impl FnOnce(&mut State, usize),
+    ) -> Result<(usize, Option<Instant>), WaypointError> {
+        let Pass {
+            mut rng,
+            head_start,
+            deadline,
+            weak,
+            parked,
+            gate,
+            id,
+            epoch,
+            poll,
+        } = pass;
+        if rng.start > rng.end {
+            return Err(ErrorKind::InvalidRange {
+                l: rng.start,
+                h: rng.end,
+            }
+            .into());
+        }
+        let arrived = Instant::now();
+        let deadline = deadline
+            .or_else(|| {
+                TIMEOUTS.with(|t| {
+                    let t = t.borrow();
+                    let timeout = t.iter().rev().find(|&&(addr, _)| addr == self.addr());
+                    timeout.map(|&(_, dt)| arrived + dt)
+                })
+            })
+            .or_else(|| {
+                let max_wait = self.config.max_wait;
+                let max_wait = max_wait.or(*Self::into_guard(DEFAULT_MAX_WAIT.lock()));
+                max_wait.map(|dt| arrived + dt)
+            });
+        let mut step = 1;
+        let (res, mut state_lck) = if rng.is_empty() {
+            let state_lck = self.state_lck();
+            (Err(ErrorKind::AlreadyPassed(state_lck.n).into()), state_lck)
+        } else {
+            let l = rng.next().expect("check rng is not empty");
+            let h = 1 + rng.last().unwrap_or(l);
+            let mut state_lck = self.state_lck();
+            if state_lck.cancelled {
+                return Err(ErrorKind::Cancelled.into());
+            }
+            if let Some(err) = state_lck.check_epoch(epoch) {
+                return Err(err.into());
+            }
+            let start_current = state_lck.n;
+            let ticket = if parked {
+                Some(state_lck.park((l, h)))
+            } else {
+                None
+            };
+            let rank = id.map(|id| state_lck.rank((l, h), id));
+            #[cfg(feature = "log")]
+            log::trace!(
+                "waiting for waypoint {}..{} (current {})",
+                l,
+                h,
+                start_current
+            );
+            let (mut state_lck, timed_out) = if self.config.soft_order {
+                (state_lck, false)
+            } else {
+                let cond = Cond {
+                    gate: gate.as_deref(),
+                    rank,
+                    epoch,
+                };
+                self.wait_escalating(state_lck, l, arrived, deadline, cond)
+            };
+            if let Some(rank) = rank {
+                // the threads behind this one may pass even if this one does not
+                if state_lck.unrank(rank) {
+                    self.cv.notify_all();
+                }
+            }
+            if let Some(ticket) = ticket {
+                if state_lck.unpark((l, h), ticket) {
+                    // the leader counted the advance
+                    state_lck.count_advance(0, 1);
+                    return Ok((state_lck.n, state_lck.target_time));
+                }
+            }
+            if state_lck.cancelled {
+                return Err(ErrorKind::Cancelled.into());
+            }
+            if let Some(err) = state_lck.check_epoch(epoch) {
+                return Err(err.into());
+            }
+            if timed_out {
+                if !poll && self.config.on_timeout == TimeoutAction::AbortWithBacktrace {
+                    // `dump` locks the state
+                    drop(state_lck);
+                    self.abort_with_backtrace(l);
+                }
+                return Err(ErrorKind::TimedOut {
+                    start_current,
+                    end_current: state_lck.n,
+                    expected: l,
+                }
+                .into());
+            }
+
+            // check the state
+            let res = match state_lck.n {
+                n if l <= n && n < h => Ok(()),
+                n if weak => return Ok((n, state_lck.target_time)),
+                _ if self.config.soft_order => {
+                    #[cfg(feature = "log")]
+                    log::warn!("expected to pass {} but sequence at {}", l, state_lck.n);
+                    state_lck.stats.out_of_order += 1;
+                    Ok(())
+                }
+                n if n >= h => Err(ErrorKind::AlreadyPassed(n).into()),
+                n => {
+                    // waiting guarantees `n >= l`, so this is a bug in `Waypoints` itself
+                    if cfg!(debug_assertions) {
+                        panic!(
+                            "passed waypoint {}..{} before schedule (current waypoint {}, next \
+                             waypoint may pass at {:?})",
+                            l, h, n, state_lck.target_time
+                        );
+                    }
+                    Err(ErrorKind::Inconsistent(n).into())
+                }
+            };
+            // a thread passed out of order by `soft_order` releases no other threads
+            let n = state_lck.n;
+            if parked && res.is_ok() && !self.config.strict_sequence && l <= n && n < h {
+                step += state_lck.release((l, h), h - n - 1);
+            }
+            (res, state_lck)
+        };
+
+        let mut passage = self.pass_locked(&mut state_lck, step, res.is_ok(), arrived, head_start);
+        let (next, scheduled) = (passage.next, state_lck.target_time);
+        // a panic of `on_pass` must not skip the notification, or the waiting threads would hang
+        let panic = match res {
+            Ok(()) => {
+                let state = &mut *state_lck;
+                panic::catch_unwind(AssertUnwindSafe(|| on_pass(state, next))).err()
+            }
+            Err(_) => None,
+        };
+        #[cfg(feature = "jitter")]
+        let jitter = state_lck.next_jitter();
+
+        // drop lock before sleeping
+        drop(state_lck);
+
+        #[cfg(feature = "log")]
+        if let Err(err) = &res {
+            log::trace!("failed to pass waypoint: {} (current {})", err, next);
+        }
+        passage.sleep(self);
+        passage.finish(self);
+        #[cfg(feature = "jitter")]
+        if res.is_ok() && jitter > Duration::ZERO {
+            self.sleeper().sleep(jitter);
+        }
+
+        if let Some(payload) = panic {
+            panic::resume_unwind(payload);
+        }
+        res.map(|()| (next, scheduled))
+    }
+
+    // advance the current number by `step` while the state is locked, updating the schedule, the
+    // records and the counters, and return what remains to be done once the state is unlocked;
+    // `ok` tells whether the waypoint was passed or the call failed
+    fn pass_locked(
+        &self,
+        state: &mut State,
+        step: usize,
+        ok: bool,
+        arrived: Instant,
+        head_start: Option<Duration>,
+    ) -> Passage {
+        let this = state.n;
+        state.n += step;
+        state.count_advance(step, ok as usize);
+        state.generation += 1;
+        self.mirror(state);
+        let next = state.n;
+        let now = Instant::now();
+        let target_time_this = state.target_time;
+        let passed = target_time_this.map_or(now, |t| std::cmp::max(now, t));
+        let head_start = state.next_head_start.take().or(head_start).or_else(|| {
+            let f = self.config.head_start_ratio?;
+            let dwell = passed.saturating_duration_since(state.last_passed?);
+            Some(dwell.mul_f64(f))
+        });
+        state.target_time = next_target_time(state.target_time, now, head_start);
+        if ok {
+            state.last_passed = Some(passed);
+        }
+
+        if let Some(&gap) = state.gaps.get(next) {
+            let t = passed + gap;
+            state.target_time = Some(state.target_time.map_or(t, |t0| std::cmp::max(t0, t)));
+        }
+        state.apply_paces(now);
+        if let Some(max) = self.config.max_head_start_target {
+            state.target_time = state.target_time.map(|t| std::cmp::min(t, now + max));
+        }
+        if state.recent_capacity > 0 && ok {
+            if state.recent.len() == state.recent_capacity {
+                state.recent.pop_front();
+            }
+            state
+                .recent
+                .push_back(EventRecord::new(this, arrived, passed));
+        }
+        if state.recording && ok {
+            state.events.push(EventRecord::new(this, arrived, passed));
+        }
+        if ok {
+            let wait = passed - arrived;
+            match state.waits.get_mut(&this) {
+                Some(times) => times.add(wait),
+                None => {
+                    state.waits.insert(this, WaitTimes::new(wait));
+                }
+            }
+        }
+        Passage {
+            this,
+            next,
+            now,
+            target_time_this,
+            wait: passed - arrived,
+            ok,
+            notify: state.should_notify(next),
+            callbacks: if ok {
+                state.enter_phases(this)
+            } else {
+                Vec::new()
+            },
+            watchers: state.watchers.clone(),
+            tees: state.tees.clone(),
+            run_at: state.take_run_at(),
+        }
+    }
+
+    fn abort_with_backtrace(&self, l: usize) -> ! {
+        eprintln!("timed out waiting for waypoint {}, aborting", l);
+        self.dump();
+        eprintln!(
+            "backtrace of the timed out thread:\n{}",
+            Backtrace::force_capture()
+        );
+        std::process::abort()
+    }
+
+    // same as `wait_for`, but print a report once the wait exceeds the threshold of
+    // `Builder::dump_after`, then either keep waiting or time out
+    fn wait_escalating<'a>(
+        &'a self,
+        state_lck: Guard<'a>,
+        l: usize,
+        arrived: Instant,
+        deadline: Option<Instant>,
+        cond: Cond<'_>,
+    ) -> (Guard<'a>, bool) {
+        let dump_after = match self.config.dump_after {
+            Some(dump_after) => dump_after,
+            None => return self.wait_for(state_lck, l, deadline, cond),
+        };
+        let threshold = arrived + dump_after.threshold;
+        if deadline.is_some_and(|d| d <= threshold) {
+            return self.wait_for(state_lck, l, deadline, cond);
+        }
+        let (state_lck, timed_out) = self.wait_for(state_lck, l, Some(threshold), cond);
+        if !timed_out {
+            return (state_lck, false);
+        }
+
+        // `dump` locks the state
+        drop(state_lck);
+        self.dump();
+        let deadline = if dump_after.fail {
+            Some(Instant::now())
+        } else {
+            deadline
+        };
+        self.wait_for(self.state_lck(), l, deadline, cond)
+    }
+
+    // wait until the current number is at least `l` and `cond` holds, or until the sequence is
+    // cancelled or leaves the epoch of `cond`; the returned `bool` is `true` if the deadline passed
+    // first
+    fn wait_for<'a>(
+        &'a self,
+        mut state_lck: Guard<'a>,
+        l: usize,
+        deadline: Option<Instant>,
+        cond: Cond<'_>,
+    ) -> (Guard<'a>, bool) {
+        let ticket = state_lck.register_waiter(l);
+        let mut woken = false;
+        let condition = |st: &mut State| {
+            if woken {
+                st.stats.wakeups += 1;
+            }
+            woken = true;
+            let wait = !st.cancelled
+                && st.check_epoch(cond.epoch).is_none()
+                && (st.n < l
+                    || cond.gate.is_some_and(|g| !g.load(Ordering::Acquire))
+                    || cond.rank.is_some_and(|r| st.yields(r)));
+            if wait {
+                st.peak_waiters = std::cmp::max(st.peak_waiters, st.waiters.len());
+            }
+            wait
+        };
+        let (mut state_lck, timed_out) = match deadline {
+            None => (
+                Self::into_guard(self.cv.wait_while(state_lck, condition)),
+                false,
+            ),
+            Some(deadline) => {
+                let dur = deadline.saturating_duration_since(Instant::now());
+                let res = self.cv.wait_timeout_while(state_lck, dur, condition);
+                let (state_lck, timeout) = Self::into_guard(res);
+                (state_lck, timeout.timed_out())
+            }
+        };
+        state_lck.waiters.remove(&ticket);
+        (state_lck, timed_out)
+    }
+}
+
+#[cfg(feature = "metrics")]
+fn record_metrics(passed: bool, wait: Duration, current: usize) {
+    if passed {
+        metrics::histogram!("waypoints.wait_seconds").record(wait);
+        metrics::counter!("waypoints.passed").increment(1);
+    }
+    metrics::gauge!("waypoints.current").set(current as f64);
+}
+
+// map each waypoint number of a comma-separated order to its position in the order
+#[cfg(feature = "env-order")]
+fn parse_order(order: &str) -> Result<HashMap<usize, usize>, String> {
+    let mut positions = HashMap::new();
+    for (p, n) in order.split(',').enumerate() {
+        let n = n.trim().parse::<usize>().map_err(|err| err.to_string())?;
+        if positions.insert(n, p).is_some() {
+            return Err(format!("waypoint {} appears more than once", n));
+        }
+    }
+    match (0..positions.len()).find(|n| !positions.contains_key(n)) {
+        Some(n) => Err(format!("waypoint {} is missing", n)),
+        None => Ok(positions),
+    }
+}
+
+// the earliest time at which the next waypoint may pass after a waypoint with `target_time` is
+// passed at time `now`
+fn next_target_time(
+    target_time: Option<Instant>,
+    now: Instant,
+    head_start: Option<Duration>,
+) -> Option<Instant> {
+    match (target_time, head_start) {
+        (Some(t), Some(dt)) => Some(std::cmp::max(now, t) + dt),
+        (Some(t), None) if now < t => Some(t),
+        (Some(_), None) => None,
+        (None, Some(dt)) => Some(now + dt),
+        (None, None) => None,
+    }
+}
+
+// the first index at which `a` and `b` differ, including the end of the shorter one
+fn first_divergence<T: PartialEq>(a: &[T], b: &[T]) -> Option<usize> {
+    match a.iter().zip(b).position(|(x, y)| x != y) {
+        Some(d) => Some(d),
+        None if a.len() != b.len() => Some(std::cmp::min(a.len(), b.len())),
+        None => None,
+    }
+}
+
+// sleep until the target time of a waypoint passed at time `now`
+fn sleep_until(target_time: Option<Instant>, now: Instant, sleeper: &dyn Sleeper) {
+    match target_time {
+        Some(t) if now < t => sleeper.sleep(t - now),
+        _ => {}
+    }
+}
+
+impl Default for Waypoints {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for Waypoints {
+    /// Render the progress compactly as `waypoint 3/10`, i.e. the current number over
+    /// [`Builder::expected_final`], or as `waypoint 3` if no final waypoint is configured.  See
+    /// [`Waypoints::dump`] for a full report.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let n = self.state_lck().n;
+        match self.config.expected_final {
+            Some(max) => write!(f, "waypoint {}/{}", n, max),
+            None => write!(f, "waypoint {}", n),
+        }
+    }
+}
+
+/// Create an array of `N` independent [`Waypoints`], e.g. for a fixed pool of sequences shared by
+/// the tests of a module.
+///
+/// `Waypoints::new` cannot be a `const fn`, since it records the time at which the `Waypoints`
+/// were created, so an array in a `static` has to be initialized lazily:
+///
+/// ```
+/// use std::sync::LazyLock;
+/// use waypoints::{waypoints_array, Waypoints};
+///
+/// static WPS: LazyLock<[Waypoints; 8]> = LazyLock::new(|| waypoints_array!(8));
+///
+/// WPS[3].point(0, None).unwrap();
+/// assert_eq!(WPS[3].passed_count(), 1);
+/// assert_eq!(WPS[4].passed_count(), 0);
+/// ```
+#[macro_export]
+macro_rules! waypoints_array {
+    ($n:expr) => {
+        [(); $n].map(|()| $crate::Waypoints::new())
+    };
+}
+
+impl Drop for Waypoints {
+    fn drop(&mut self) {
+        if let Some(expected) = self.config.expected_final {
+            let n = Self::into_guard(self.state.get_mut()).n;
+            if n < expected && !std::thread::panicking() {
+                panic!(
+                    "waypoint sequence incomplete: stopped at {}, expected {}",
+                    n, expected
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn send_sync() {
+        assert_send_sync::<Waypoints>();
+        assert_send_sync::<Arc<Waypoints>>();
+        assert_send_sync::<WeakWaypoints>();
+        assert_send_sync::<WaypointsGroup>();
+        assert_send_sync::<WaypointsPool>();
+        assert_send_sync::<PhasedWaypoints>();
+        assert_send_sync::<WaitFuture>();
+        assert_send_sync::<WaypointError>();
+    }
+
+    #[test]
+    fn error_on_duplicate_waypoint() {
+        let w = Waypoints::new();
+        w.point(0, None).unwrap();
+        assert!(w.point(0, None).is_err());
+    }
+
+    #[test]
+    fn point_reentrant() {
+        let w = Waypoints::new_arc();
+        w.point_reentrant(0, None).unwrap();
+        w.point_reentrant(0, None).unwrap();
+        assert_eq!(w.passed_count(), 1);
+        assert_eq!(w.point(0, None), Err(ErrorKind::AlreadyPassed(1).into()));
+        let t = {
+            let w = w.clone();
+            std::thread::spawn(move || w.point_reentrant(0, None))
+        };
+        assert_eq!(t.join().unwrap(), Err(ErrorKind::AlreadyPassed(2).into()));
+        w.reset();
+        w.point_reentrant(0, None).unwrap();
+        assert_eq!(w.passed_count(), 1);
+    }
+
+    #[test]
+    fn first_come() {
+        let w = Waypoints::new();
+        w.first_come(1);
+        w.point(0, None).unwrap();
+        let passed: Vec<_> = std::thread::scope(|s| {
+            let threads: Vec<_> = (0..3)
+                .map(|_| s.spawn(|| w.point_first_come(1, None).unwrap()))
+                .collect();
+            threads.into_iter().map(|t| t.join().unwrap()).collect()
+        });
+        assert_eq!(passed.iter().filter(|&&p| p).count(), 1);
+        assert_eq!(w.point(1, None), Ok(()));
+        w.point(2, None).unwrap();
+        assert_eq!(w.passed_count(), 3);
+    }
+
+    #[test]
+    fn point_epoch() {
+        let w = Waypoints::new_arc();
+        let epoch = w.epoch();
+        w.point_epoch(0, epoch, None).unwrap();
+        let t = {
+            let w = w.clone();
+            std::thread::spawn(move || w.point_epoch(2, epoch, None))
+        };
+        while w.waiters() == 0 {
+            std::thread::yield_now();
+        }
+        w.reset();
+        assert_eq!(
+            t.join().unwrap(),
+            Err(ErrorKind::StaleEpoch {
+                expected: epoch,
+                current: epoch + 1
+            }
+            .into())
+        );
+        assert!(w.point_epoch(0, epoch, None).is_err());
+        w.point_epoch(0, w.epoch(), None).unwrap();
+    }
+
+    #[test]
+    fn cancel() {
+        let w = Waypoints::new_arc();
+        let t = {
+            let w = w.clone();
+            std::thread::spawn(move || w.point(1, None))
+        };
+        while w.waiters() == 0 {
+            std::thread::yield_now();
+        }
+        w.cancel();
+        assert_eq!(t.join().unwrap(), Err(ErrorKind::Cancelled.into()));
+        assert_eq!(w.point(0, None), Err(ErrorKind::Cancelled.into()));
+        assert_eq!(w.own_run(0, 2), Err(ErrorKind::Cancelled.into()));
+        assert_eq!(w.passed_count(), 0);
+        w.reset();
+        assert!(!w.is_cancelled());
+        w.point(0, None).unwrap();
+    }
+
+    #[test]
+    fn error_location() {
+        let w = Waypoints::new();
+        w.point(0, None).unwrap();
+        let line = line!() + 1;
+        let err = w.point(0, None).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::AlreadyPassed(1));
+        assert_eq!(
+            (err.location().file(), err.location().line()),
+            (file!(), line)
+        );
+        assert!(err
+            .to_string()
+            .ends_with(&format!(" at {}", err.location())));
+    }
+
+    #[test]
+    fn sequence() {
+        let v_point = Arc::new(Mutex::new(Vec::new()));
+        let v_range = Arc::new(Mutex::new(Vec::new()));
+        let w = Waypoints::new_arc();
+        let mut threads = Vec::new();
+
+        threads.push({
+            let v_point = v_point.clone();
+            let v_range = v_range.clone();
+            let w = w.clone();
+            std::thread::spawn(move || {
+                v_point.lock().unwrap().push(0);
+                w.point(0, None).unwrap();
+                w.point(3, None).unwrap();
+                v_point.lock().unwrap().push(3);
+                v_point.lock().unwrap().push(4);
+                v_point.lock().unwrap().push(5);
+                v_point.lock().unwrap().push(6);
+                w.point(4, None).unwrap();
+                w.point(7, None).unwrap();
+                v_point.lock().unwrap().push(9);
+                w.point(8, None).unwrap();
+                w.range(9..12, None).unwrap();
+                for _ in 0..3 {
+                    std::thread::yield_now();
+                    v_range.lock().unwrap().push(11);
+                }
+            })
+        });
+
+        threads.push({
+            let v_point = v_point.clone();
+            let v_range = v_range.clone();
+            let w = w.clone();
+            std::thread::spawn(move || {
+                w.point(1, None).unwrap();
+                v_point.lock().unwrap().push(1);
+                v_point.lock().unwrap().push(2);
+                w.point(2, None).unwrap();
+                w.range(9..12, None).unwrap();
+                for _ in 0..3 {
+                    std::thread::yield_now();
+                    v_range.lock().unwrap().push(22);
+                }
+            })
+        });
+
+        threads.push({
+            let v_point = v_point.clone();
+            let v_range = v_range.clone();
+            std::thread::spawn(move || {
+                w.point(5, None).unwrap();
+                v_point.lock().unwrap().push(7);
+                v_point.lock().unwrap().push(8);
+                w.point(6, None).unwrap();
+                w.range(9..12, None).unwrap();
+                for _ in 0..3 {
+                    std::thread::yield_now();
+                    v_range.lock().unwrap().push(33);
+                }
+            })
+        });
+
+        threads.into_iter().for_each(|t| {
+            t.join().ok();
+        });
 
         let v_point = Arc::try_unwrap(v_point).unwrap().into_inner().unwrap();
         let v_range = Arc::try_unwrap(v_range).unwrap().into_inner().unwrap();
         println!("points are ordered: {:?}", &v_point);
         println!("range can provide concurrency (no order): {:?}", &v_range);
-        assert_eq!(v_point, (0..10).into_iter().collect::<Vec<_>>());
+        assert_eq!(v_point, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn turns() {
+        let w = Waypoints::new();
+        w.record(true);
+        std::thread::scope(|s| {
+            for t in 0..3 {
+                let w = &w;
+                let thread = std::thread::Builder::new().name(t.to_string());
+                thread
+                    .spawn_scoped(s, move || {
+                        let mut seq = wp_thread!(w; turn = t, threads = 3);
+                        assert_eq!(seq.next(), t);
+                        wp!(seq);
+                        wp!(seq, Duration::ZERO);
+                    })
+                    .unwrap();
+            }
+        });
+        let names: Vec<_> = w.events().into_iter().map(|e| e.thread_name).collect();
+        let expected: Vec<_> = (0..6).map(|n| Some((n % 3).to_string())).collect();
+        assert_eq!(names, expected);
+    }
+
+    #[test]
+    fn barrier() {
+        let w = Waypoints::new_arc();
+        let arrived = Arc::new(Mutex::new(0));
+        let threads = (0..3)
+            .map(|_| {
+                let w = w.clone();
+                let arrived = arrived.clone();
+                std::thread::spawn(move || {
+                    let b = w.as_barrier(1, 3);
+                    *arrived.lock().unwrap() += 1;
+                    let leader = b.wait().unwrap();
+                    assert_eq!(*arrived.lock().unwrap(), 3);
+                    leader
+                })
+            })
+            .collect::<Vec<_>>();
+
+        w.point(0, None).unwrap();
+        let leaders = threads
+            .into_iter()
+            .map(|t| t.join().unwrap())
+            .filter(|&leader| leader)
+            .count();
+        assert_eq!(leaders, 1);
+        assert!(w.barrier(1, 3).is_err());
+    }
+
+    #[test]
+    fn cyclic_barrier() {
+        let w = Waypoints::new();
+        std::thread::scope(|s| {
+            for _ in 0..2 {
+                s.spawn(|| {
+                    let b = w.cyclic_barrier(0, 2, 3);
+                    let leaders = (0..5).filter(|_| b.wait().unwrap()).count();
+                    assert!(leaders <= 3);
+                });
+            }
+        });
+        assert_eq!(w.passed_count(), 6);
+        assert_eq!(w.cyclic_barrier(0, 2, 3).wait(), Ok(false));
+    }
+
+    #[test]
+    fn deadline() {
+        let w = Waypoints::new();
+        let deadline = Instant::now() + Duration::from_millis(50);
+        w.point_deadline(0, None, deadline).unwrap();
+        assert_eq!(
+            w.point_deadline(2, None, deadline),
+            Err(ErrorKind::TimedOut {
+                start_current: 1,
+                end_current: 1,
+                expected: 2
+            }
+            .into())
+        );
+        w.point_deadline(1, None, deadline).unwrap();
+        assert_eq!(
+            w.point_deadline(1, None, deadline),
+            Err(ErrorKind::AlreadyPassed(2).into())
+        );
+    }
+
+    #[test]
+    fn try_point() {
+        let w = Waypoints::new();
+        assert_eq!(w.try_point(1), Ok(false));
+        assert_eq!(w.try_point(0), Ok(true));
+        assert_eq!(w.try_point(0), Err(ErrorKind::AlreadyPassed(1).into()));
+        w.point(1, Duration::from_secs(3600)).unwrap();
+        assert_eq!(w.try_point(2), Ok(false));
+        assert_eq!(w.passed_count(), 2);
+    }
+
+    #[test]
+    fn point_timeout() {
+        let w = Waypoints::new();
+        let dt = Duration::from_millis(20);
+        let res = w.point_timeout(1, None, dt);
+        assert!(matches!(
+            res.unwrap_err().kind(),
+            ErrorKind::TimedOut { expected: 1, .. }
+        ));
+        w.point_timeout(0, Duration::from_millis(50), dt).unwrap();
+        let t0 = Instant::now();
+        w.point_timeout(1, None, dt).unwrap();
+        assert!(t0.elapsed() > dt);
+        assert_eq!(
+            w.point_timeout(1, None, dt),
+            Err(ErrorKind::AlreadyPassed(2).into())
+        );
+    }
+
+    #[test]
+    fn set_past_waiting_range() {
+        let w = Waypoints::new_arc();
+        let t = {
+            let w = w.clone();
+            std::thread::spawn(move || w.range(5..8, None))
+        };
+        while w.waiters() == 0 {
+            std::thread::yield_now();
+        }
+        w.set(9, None).unwrap();
+        assert_eq!(t.join().unwrap(), Err(ErrorKind::AlreadyPassed(9).into()));
+    }
+
+    #[test]
+    fn passed_count() {
+        let w = Waypoints::new();
+        assert_eq!(w.passed_count(), 0);
+        w.point(0, None).unwrap();
+        w.range(1..3, None).unwrap();
+        assert_eq!(w.passed_count(), 2);
+    }
+
+    #[test]
+    fn validate() {
+        let w = Waypoints::new();
+        assert_eq!(w.validate(), Ok(()));
+        w.declare_participant(vec![0, 3]);
+        w.declare_participant(vec![1, 5]);
+        assert_eq!(w.validate(), Err(vec![2, 4]));
+        w.declare_participant(vec![2, 4]);
+        assert_eq!(w.validate(), Ok(()));
+
+        let w = Waypoints::builder().expected_final(4).build();
+        w.declare_participant(vec![0, 1, 2]);
+        assert_eq!(w.validate(), Err(vec![3]));
+        w.set(4, None).unwrap();
+    }
+
+    #[test]
+    fn display() {
+        let w = Waypoints::new();
+        w.point(0, None).unwrap();
+        assert_eq!(w.to_string(), "waypoint 1");
+        let w = Waypoints::builder().expected_final(2).build();
+        w.point(0, None).unwrap();
+        assert_eq!(w.to_string(), "waypoint 1/2");
+        w.point(1, None).unwrap();
+    }
+
+    #[test]
+    fn try_snapshot() {
+        let w = Waypoints::new();
+        w.point(0, None).unwrap();
+        let snapshot = w.try_snapshot().unwrap();
+        assert_eq!(snapshot.current, 1);
+        assert!(snapshot.waiting.is_empty());
+        let state_lck = w.state_lck();
+        assert_eq!(w.try_snapshot(), None);
+        drop(state_lck);
+    }
+
+    #[test]
+    fn assert_deterministic() {
+        let w = Waypoints::new();
+        let body = |w: &Waypoints| {
+            let obs = Mutex::new(Vec::new());
+            std::thread::scope(|s| {
+                s.spawn(|| {
+                    w.point(1, None).unwrap();
+                    obs.lock().unwrap().push(1);
+                    w.point(2, None).unwrap();
+                });
+                s.spawn(|| {
+                    obs.lock().unwrap().push(0);
+                    w.point(0, None).unwrap();
+                });
+            });
+            obs.into_inner().unwrap()
+        };
+        w.assert_deterministic(5, body);
+
+        let mut runs = 0;
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            w.assert_deterministic(2, |_| {
+                runs += 1;
+                vec![runs]
+            })
+        }));
+        assert!(res.is_err());
+        assert_eq!(first_divergence(&[0, 1], &[0, 1, 2]), Some(2));
+    }
+
+    #[test]
+    fn wait_histogram() {
+        let w = Waypoints::new();
+        let dt = Duration::from_millis(20);
+        w.point(0, dt).unwrap();
+        w.point(1, None).unwrap();
+        w.reset();
+        w.point(0, None).unwrap();
+        let histogram = w.wait_histogram();
+        assert_eq!(histogram.len(), 2);
+        let (n, min, mean, max) = histogram[1];
+        assert_eq!(n, 1);
+        assert!(min == max && mean == max && max > dt / 2);
+        let (n, min, mean, max) = histogram[0];
+        assert_eq!(n, 0);
+        assert!(min <= mean && mean <= max && max < dt / 2);
+    }
+
+    #[test]
+    fn is_time_gated() {
+        let w = Waypoints::new();
+        assert!(!w.is_time_gated());
+        w.point(0, Duration::from_secs(3600)).unwrap();
+        assert!(w.is_time_gated());
+        w.set_force(0, None).unwrap();
+        assert!(!w.is_time_gated());
+    }
+
+    #[test]
+    fn total_advances() {
+        let w = Waypoints::new();
+        for _ in 0..3 {
+            w.point(0, None).unwrap();
+            w.point(1, None).unwrap();
+            w.reset();
+        }
+        assert_eq!(w.total_advances(), 6);
+        w.skip_to(3).unwrap();
+        w.own_run(3, 4).unwrap();
+        w.set(9, None).unwrap();
+        assert_eq!(w.total_advances(), 11);
+    }
+
+    #[test]
+    fn passed() {
+        let w = Waypoints::new_arc();
+        let t = {
+            let w = w.clone();
+            std::thread::spawn(move || w.point(1, None).unwrap())
+        };
+        w.point(0, None).unwrap();
+        w.wait_completed(2);
+        t.join().unwrap();
+        w.own_run(2, 4).unwrap();
+        assert!(w.point(0, None).is_err());
+        assert_eq!(w.passed(), 5);
+    }
+
+    #[test]
+    fn point_with_fn_panics() {
+        let w = Waypoints::new_arc();
+        let t = {
+            let w = w.clone();
+            std::thread::spawn(move || w.point(1, None))
+        };
+        while w.waiters() == 0 {
+            std::thread::yield_now();
+        }
+        let res = panic::catch_unwind(AssertUnwindSafe(|| {
+            w.point_with_fn(0, None, |_| panic!("in f"))
+        }));
+        assert!(res.is_err());
+        assert_eq!(t.join().unwrap(), Ok(()));
+        assert_eq!(w.passed(), 2);
+    }
+
+    #[test]
+    fn current() {
+        let w = Waypoints::new();
+        assert_eq!((w.current(), w.scheduled_time()), (0, None));
+        w.point(0, Duration::from_secs(3600)).unwrap();
+        assert_eq!(w.current(), 1);
+        assert!(w.scheduled_time().is_some_and(|t| t > Instant::now()));
+    }
+
+    #[test]
+    fn current_relaxed() {
+        let w = Waypoints::new();
+        w.point(0, None).unwrap();
+        assert_eq!(w.current_relaxed(), 1);
+        w.set(5, None).unwrap();
+        assert_eq!(w.current_relaxed(), 5);
+    }
+
+    #[test]
+    fn is_complete() {
+        let w = Waypoints::builder().expected_final(2).build();
+        assert!(!w.is_complete(2));
+        w.range(0..2, None).unwrap();
+        w.point(1, None).unwrap();
+        assert!(w.is_complete(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "waypoint sequence stalled at 2, expected 3")]
+    fn expect_all() {
+        let w = Waypoints::new();
+        {
+            let _guard = w.expect_all(1);
+            w.point(0, None).unwrap();
+        }
+        let _guard = w.expect_all(3);
+        w.point(1, None).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "original")]
+    fn expect_all_while_panicking() {
+        let w = Waypoints::new();
+        let _guard = w.expect_all(1);
+        panic!("original");
+    }
+
+    #[test]
+    #[should_panic(expected = "stopped at 1, expected 2")]
+    fn incomplete_on_drop() {
+        let w = Waypoints::builder().expected_final(2).build();
+        w.point(0, None).unwrap();
+    }
+
+    #[test]
+    fn skip_notifications() {
+        let w = Waypoints::new_arc();
+        let threads = (0..10)
+            .map(|i| {
+                let w = w.clone();
+                std::thread::spawn(move || w.point(1001 + i, None).unwrap())
+            })
+            .collect::<Vec<_>>();
+        while w.state_lck().waiters.len() < 10 {
+            std::thread::yield_now();
+        }
+
+        (0..1000).for_each(|n| w.point(n, None).unwrap());
+        let stats = w.stats();
+        assert_eq!(stats.wakeups, 0);
+        assert_eq!(stats.notifications, 0);
+        assert_eq!(stats.skipped_notifications, 1000);
+
+        w.point(1000, None).unwrap();
+        threads.into_iter().for_each(|t| t.join().unwrap());
+    }
+
+    #[test]
+    fn point_phase() {
+        let w = Waypoints::builder().phase_parties(3).build_arc();
+        let arrived = Arc::new(Mutex::new(Vec::new()));
+        let threads = (0..3)
+            .map(|_| {
+                let w = w.clone();
+                let arrived = arrived.clone();
+                std::thread::spawn(move || {
+                    for phase in 0..4 {
+                        arrived.lock().unwrap().push(phase);
+                        assert_eq!(w.point_phase(), phase);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        threads.into_iter().for_each(|t| t.join().unwrap());
+
+        // no thread arrives in a phase before all threads arrived in the previous phase
+        let arrived = arrived.lock().unwrap();
+        assert!(arrived.windows(2).all(|p| p[0] <= p[1]));
+    }
+
+    #[test]
+    fn report() {
+        let w = Waypoints::builder().expected_final(5).build_arc();
+        let threads = [3, 3, 4]
+            .iter()
+            .map(|&n| {
+                let w = w.clone();
+                std::thread::spawn(move || w.range(n..5, None))
+            })
+            .collect::<Vec<_>>();
+        while w.waiters() < 3 {
+            std::thread::yield_now();
+        }
+        w.point(0, Some(Duration::from_secs(60))).unwrap();
+
+        let report = w.report();
+        println!("{}", report);
+        let mut lines = report.lines();
+        assert_eq!(lines.next(), Some("waypoints: current 1 of 5"));
+        assert!(lines.next().unwrap().contains("may pass in"));
+        assert_eq!(lines.next(), Some("  epoch 0, not cancelled"));
+        assert_eq!(lines.next(), Some("  3 threads waiting"));
+        assert_eq!(lines.next(), Some("    2 waiting for 3"));
+        assert_eq!(lines.next(), Some("    1 waiting for 4"));
+        assert_eq!(lines.next(), None);
+
+        w.set(5, None).unwrap();
+        threads
+            .into_iter()
+            .for_each(|t| assert!(t.join().unwrap().is_err()));
+        w.cancel();
+        assert!(w.report().contains("\n  epoch 0, cancelled\n"));
+    }
+
+    #[test]
+    fn with_capacity() {
+        let w = Waypoints::with_capacity(16);
+        w.record(true);
+        (0..16).for_each(|n| w.point(n, None).unwrap());
+        assert_eq!(w.state_lck().events.capacity(), 16);
+    }
+
+    // The `Inconsistent` branch of `advance_with` cannot be reached: a wait only ends with the
+    // current number below the range if the sequence was cancelled, reset to a new epoch or timed
+    // out, and each of those returns before the branch.  This test only guards the nearby path in
+    // which `set_force` moves the state back below a waiting range.
+    #[test]
+    fn set_before_waiting_range() {
+        let w = Waypoints::new_arc();
+        w.point(0, None).unwrap();
+        let t = {
+            let w = w.clone();
+            std::thread::spawn(move || w.range(2..4, None))
+        };
+        while w.waiters() < 1 {
+            std::thread::yield_now();
+        }
+        // moving the state back wakes the waiting thread, which keeps waiting
+        w.set_force(0, None).unwrap();
+        w.point(0, None).unwrap();
+        w.point(1, None).unwrap();
+        assert_eq!(t.join().unwrap(), Ok(()));
+    }
+
+    #[test]
+    fn reset_full() {
+        let w = Waypoints::new_arc();
+        w.record(true);
+        w.record_last(4);
+        let t = {
+            let w = w.clone();
+            std::thread::spawn(move || w.point(1, None))
+        };
+        while w.waiters() == 0 {
+            std::thread::yield_now();
+        }
+        w.point(0, None).unwrap();
+        t.join().unwrap().unwrap();
+        let survivors = |w: &Waypoints| {
+            (
+                w.events().len(),
+                w.recent().len(),
+                w.wait_histogram().len(),
+                w.stats().wakeups > 0,
+                w.peak_waiters(),
+                w.total_advances(),
+                w.passed(),
+            )
+        };
+        w.reset();
+        assert_eq!(w.passed_count(), 0);
+        assert_eq!(survivors(&w), (2, 2, 2, true, 1, 2, 2));
+        w.reset_full();
+        assert_eq!(w.passed_count(), 0);
+        assert_eq!(survivors(&w), (0, 0, 0, false, 0, 0, 0));
+        assert_eq!(w.stats().skipped_notifications, 0);
+        w.point(0, None).unwrap();
+        assert_eq!(w.events().len(), 1);
+    }
+
+    #[test]
+    fn reset_counter_keep_time() {
+        let w = Waypoints::new();
+        let dt = Duration::from_millis(50);
+        let t0 = Instant::now();
+        w.point(0, dt).unwrap();
+        w.reset_counter_keep_time();
+        w.point(0, None).unwrap();
+        assert!(t0.elapsed() >= dt);
+        w.reset();
+        let t1 = Instant::now();
+        w.point(0, None).unwrap();
+        assert!(t1.elapsed() < dt);
+    }
+
+    #[test]
+    fn point_gated() {
+        let w = Waypoints::new_arc();
+        let gate = Arc::new(AtomicBool::new(false));
+        let t = {
+            let (w, gate) = (w.clone(), gate.clone());
+            std::thread::spawn(move || w.point_gated(1, gate, None))
+        };
+        w.point(0, None).unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(w.passed_count(), 1);
+        gate.store(true, Ordering::Release);
+        w.notify_gate();
+        t.join().unwrap().unwrap();
+        assert_eq!(w.passed_count(), 2);
+    }
+
+    #[test]
+    fn skip_to() {
+        let w = Waypoints::new();
+        w.skip_to(3).unwrap();
+        w.skip_to(1).unwrap();
+        assert_eq!(w.passed_count(), 3);
+        let w = Waypoints::builder().strict_sequence(true).build();
+        assert_eq!(w.skip_to(2), Err(ErrorKind::Gap { from: 0, to: 2 }.into()));
+    }
+
+    #[test]
+    fn pace_with() {
+        let w = Waypoints::new_arc();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let pacer = w.pace_with(rx);
+        let worker = {
+            let w = w.clone();
+            std::thread::spawn(move || w.point(2, None))
+        };
+        tx.send(()).unwrap();
+        tx.send(()).unwrap();
+        worker.join().unwrap().unwrap();
+        drop(tx);
+        pacer.join().unwrap();
+        assert_eq!(w.passed_count(), 3);
+        assert_eq!((w.total_advances(), w.passed()), (3, 3));
+    }
+
+    #[test]
+    fn tee() {
+        let w = Waypoints::new();
+        let observer = Waypoints::new_arc();
+        w.tee(observer.clone());
+        w.point(0, None).unwrap();
+        w.point(1, None).unwrap();
+        assert_eq!(observer.passed_count(), 2);
+        observer.point(2, None).unwrap();
+        w.point(2, None).unwrap();
+        assert_eq!(observer.passed_count(), 3);
+    }
+
+    #[test]
+    fn own_run() {
+        let w = Waypoints::new_arc();
+        let t = {
+            let w = w.clone();
+            std::thread::spawn(move || w.own_run(1, 1000))
+        };
+        w.point(0, None).unwrap();
+        w.point(1001, None).unwrap();
+        t.join().unwrap().unwrap();
+        assert_eq!(w.own_run(5, 10), Err(ErrorKind::AlreadyPassed(1002).into()));
+        assert_eq!(w.passed_count(), 1002);
+    }
+
+    #[test]
+    fn pace_after() {
+        let w = Waypoints::new();
+        let dt = Duration::from_millis(20);
+        w.pace_after(1, dt);
+        w.point(0, None).unwrap();
+        let t0 = Instant::now();
+        w.point(1, None).unwrap();
+        w.point(2, None).unwrap();
+        assert!(t0.elapsed() >= dt);
+        let t0 = Instant::now();
+        w.pace_after(0, dt);
+        w.point(3, None).unwrap();
+        w.point(4, None).unwrap();
+        assert!(t0.elapsed() >= dt);
+    }
+
+    #[test]
+    fn run_at() {
+        let w = Waypoints::new_arc();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let count = |calls: &Arc<AtomicUsize>| {
+            let calls = calls.clone();
+            move || {
+                calls.fetch_add(1, Ordering::SeqCst);
+            }
+        };
+        w.run_at(2, count(&calls));
+        w.point(0, None).unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        w.point(1, None).unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        w.point(2, None).unwrap();
+        w.run_at(1, count(&calls));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        w.run_at(5, count(&calls));
+        w.set(9, None).unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn soft_order() {
+        let w = Waypoints::builder().soft_order(true).build();
+        w.point(1, None).unwrap();
+        w.point(0, None).unwrap();
+        w.point(2, None).unwrap();
+        assert_eq!(w.passed_count(), 3);
+        assert_eq!(w.stats().out_of_order, 2);
+    }
+
+    #[test]
+    fn soft_order_parked_late() {
+        let w = Waypoints::builder().soft_order(true).build();
+        w.set(5, None).unwrap();
+        w.range_parked(1..3, None).unwrap();
+        w.range_parked(7..9, None).unwrap();
+        assert_eq!(w.passed_count(), 7);
+        assert_eq!(w.stats().out_of_order, 2);
+    }
+
+    #[test]
+    fn set_backward() {
+        let w = Waypoints::new();
+        (0..3).for_each(|i| w.point(i, None).unwrap());
+        assert_eq!(
+            w.set(0, None),
+            Err(ErrorKind::Backward { from: 3, to: 0 }.into())
+        );
+        assert_eq!(w.passed_count(), 3);
+        w.set_force(0, None).unwrap();
+        assert_eq!(w.passed_count(), 0);
+    }
+
+    #[test]
+    fn strict_sequence() {
+        let w = Waypoints::builder().strict_sequence(true).build();
+        assert_eq!(
+            w.set(2, None),
+            Err(ErrorKind::Gap { from: 0, to: 2 }.into())
+        );
+        w.set(1, None).unwrap();
+        w.point(1, None).unwrap();
+        w.set_force(0, None).unwrap();
+        assert_eq!(w.passed_count(), 0);
+    }
+
+    #[test]
+    fn point_push() {
+        let obs = Arc::new(Mutex::new(Vec::new()));
+        let w = Waypoints::new_arc();
+        let t = {
+            let (obs, w) = (obs.clone(), w.clone());
+            std::thread::spawn(move || {
+                w.point_push(1, None, &obs, 1).unwrap();
+                w.point_push(3, None, &obs, 3).unwrap();
+            })
+        };
+        w.point_push(0, None, &obs, 0).unwrap();
+        w.point_push(2, None, &obs, 2).unwrap();
+        t.join().unwrap();
+        assert!(w.point_push(2, None, &obs, 4).is_err());
+        assert_eq!(*obs.lock().unwrap(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn point_with_fn() {
+        let w = Waypoints::new();
+        let seen = AtomicUsize::new(0);
+        let store = |next| seen.store(next, Ordering::SeqCst);
+        w.point_with_fn(0, None, store).unwrap();
+        assert_eq!(seen.load(Ordering::SeqCst), 1);
+        assert!(w.point_with_fn(0, None, |_| unreachable!()).is_err());
+    }
+
+    #[test]
+    fn invalid_range() {
+        let w = Waypoints::new();
+        #[allow(clippy::reversed_empty_ranges)]
+        let res = w.range(5..3, None);
+        assert_eq!(res, Err(ErrorKind::InvalidRange { l: 5, h: 3 }.into()));
+        assert_eq!(w.passed_count(), 0);
+    }
+
+    #[test]
+    fn range_parked() {
+        let w = Waypoints::new_arc();
+        let threads: Vec<_> = (0..3)
+            .map(|_| {
+                let w = w.clone();
+                std::thread::spawn(move || w.range_parked(1..5, None))
+            })
+            .collect();
+        while w.waiters() < 3 {
+            std::thread::yield_now();
+        }
+        w.point(0, None).unwrap();
+        threads.into_iter().for_each(|t| t.join().unwrap().unwrap());
+        assert_eq!(w.passed_count(), 4);
+        w.range_parked(1..5, None).unwrap();
+        assert_eq!(w.passed_count(), 5);
+        assert!(w.range_parked(1..5, None).is_err());
+    }
+
+    #[test]
+    fn range_by() {
+        let w = Waypoints::new_arc();
+        let threads: Vec<_> = [2, 0, 3, 1]
+            .iter()
+            .map(|&id| {
+                let w = w.clone();
+                let pass = Pass {
+                    id: Some(id),
+                    ..Pass::new(1..5, None)
+                };
+                std::thread::spawn(move || (id, w.advance(pass)))
+            })
+            .collect();
+        while w.waiters() < 4 {
+            std::thread::yield_now();
+        }
+        w.point(0, None).unwrap();
+        for t in threads {
+            let (id, next) = t.join().unwrap();
+            assert_eq!(next, Ok(id as usize + 2));
+        }
+        assert!(w.range_by(1..5, 0, None).is_err());
+    }
+
+    #[test]
+    fn range_fifo() {
+        let w = Waypoints::new_arc();
+        let threads: Vec<_> = (0..4)
+            .map(|i| {
+                let pass = Pass {
+                    id: Some(0),
+                    ..Pass::new(1..5, None)
+                };
+                let t = {
+                    let w = w.clone();
+                    std::thread::spawn(move || w.advance(pass))
+                };
+                while w.waiters() <= i {
+                    std::thread::yield_now();
+                }
+                t
+            })
+            .collect();
+        w.point(0, None).unwrap();
+        for (i, t) in threads.into_iter().enumerate() {
+            assert_eq!(t.join().unwrap(), Ok(i + 2));
+        }
+        assert!(w.range_fifo(1..5, None).is_err());
+    }
+
+    #[test]
+    fn point_weak() {
+        let w = Waypoints::new_arc();
+        let t = {
+            let w = w.clone();
+            std::thread::spawn(move || w.point_weak(2, None))
+        };
+        while w.waiters() == 0 {
+            std::thread::yield_now();
+        }
+        w.set(4, None).unwrap();
+        assert_eq!(t.join().unwrap(), Ok(()));
+        w.point_weak(4, None).unwrap();
+        w.point(5, None).unwrap();
+    }
+
+    #[test]
+    fn point_scheduled() {
+        let w = Waypoints::new();
+        let dt = Duration::from_millis(20);
+        let t0 = Instant::now();
+        let scheduled = w.point_scheduled(0, dt).unwrap().unwrap();
+        assert!(scheduled >= t0 + dt);
+        // the second call waits out the head start, so it imposes no delay of its own
+        let next = w.point_scheduled(1, None).unwrap();
+        assert!(Instant::now() >= scheduled);
+        assert!(next.is_none_or(|t| t <= scheduled));
+    }
+
+    #[test]
+    fn insert_before() {
+        let w = Waypoints::new();
+        w.point(0, None).unwrap();
+        w.insert_before(1);
+        w.range(1..2, None).unwrap();
+        w.point(1, None).unwrap();
+        w.point(2, None).unwrap();
+        assert_eq!(w.passed_count(), 4);
+    }
+
+    #[test]
+    fn alias() {
+        let w = Waypoints::new();
+        w.alias(3, 1);
+        w.alias(1, 0);
+        w.point(3, None).unwrap();
+        assert_eq!(w.point(0, None), Err(ErrorKind::AlreadyPassed(1).into()));
+        w.alias(1, 1);
+        w.point(2, None).unwrap();
+        assert_eq!(w.point(1, None), Err(ErrorKind::AlreadyPassed(3).into()));
+
+        // cycles resolve to the last number before the cycle repeats
+        w.alias(5, 4);
+        w.alias(4, 5);
+        w.point(5, None).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "jitter")]
+    fn fuzz_until() {
+        let w = Waypoints::new();
+        let mut runs = 0;
+        let body = |w: &Waypoints| {
+            runs += 1;
+            w.point(0, None).unwrap();
+            vec![w.passed_count()]
+        };
+        assert!(!w.fuzz_until(body, |obs| obs == [2], 3));
+        assert_eq!(runs, 3);
+        assert!(w.state_lck().jitter.is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "env-order")]
+    fn order() {
+        assert!(parse_order("0,1,1").is_err());
+        assert!(parse_order("0,2").is_err());
+        assert!(parse_order("0,x").is_err());
+
+        let w = Waypoints::new_arc();
+        w.state_lck().order = parse_order("0, 2, 1").unwrap();
+        let t = {
+            let w = w.clone();
+            std::thread::spawn(move || w.point(1, None))
+        };
+        w.point(0, None).unwrap();
+        w.point(2, None).unwrap();
+        t.join().unwrap().unwrap();
+        w.point(3, None).unwrap();
+    }
+
+    #[test]
+    fn phase_enter() {
+        let w = Waypoints::new_arc();
+        let entered = Arc::new(Mutex::new(Vec::new()));
+        w.define_phase("setup", 0..2);
+        w.define_phase("run", 2..5);
+        for name in ["setup", "run"] {
+            let w2 = w.clone();
+            let entered = entered.clone();
+            w.on_phase_enter(name, move || {
+                entered.lock().unwrap().push((name, w2.passed_count()));
+            });
+        }
+
+        (0..6).for_each(|n| w.point(n, None).unwrap());
+        w.reset();
+        (0..3).for_each(|n| w.point(n, None).unwrap());
+        assert_eq!(*entered.lock().unwrap(), vec![("setup", 1), ("run", 3)]);
+    }
+
+    #[test]
+    fn wait_any() {
+        let a = Waypoints::new_arc();
+        let b = Waypoints::new_arc();
+        let t = {
+            let (a, b) = (a.clone(), b.clone());
+            std::thread::spawn(move || crate::wait_any(&[(&a, 2), (&b, 1)]))
+        };
+        while a.state_lck().watchers.is_empty() {
+            std::thread::yield_now();
+        }
+        a.point(0, None).unwrap();
+        b.point(0, None).unwrap();
+        assert_eq!(t.join().unwrap(), 1);
+        assert!(a.state_lck().watchers.is_empty());
+        assert!(b.state_lck().watchers.is_empty());
+    }
+
+    #[test]
+    fn waiter_order() {
+        let w = Waypoints::new_arc();
+        let threads: Vec<_> = [3, 1, 2]
+            .iter()
+            .enumerate()
+            .map(|(i, &n)| {
+                while w.waiters() < i {
+                    std::thread::yield_now();
+                }
+                let w = w.clone();
+                std::thread::spawn(move || w.point(n, None))
+            })
+            .collect();
+        while w.waiters() < 3 {
+            std::thread::yield_now();
+        }
+        assert_eq!(w.waiter_order(), vec![3, 1, 2]);
+        assert_eq!(w.try_snapshot().unwrap().waiting, vec![1, 2, 3]);
+        w.point(0, None).unwrap();
+        threads.into_iter().for_each(|t| t.join().unwrap().unwrap());
+        assert!(w.waiter_order().is_empty());
+    }
+
+    #[test]
+    fn advance_on_drop() {
+        let obs = Mutex::new(Vec::new());
+        let w = Waypoints::new();
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                let _guard = w.advance_on_drop(1);
+                obs.lock().unwrap().push(0);
+            });
+            w.point(0, None).unwrap();
+            w.point(2, None).unwrap();
+            obs.lock().unwrap().push(1);
+        });
+        assert_eq!(obs.into_inner().unwrap(), vec![0, 1]);
+        drop(w.advance_on_drop(0));
+        assert_eq!(w.passed_count(), 4);
+    }
+
+    #[test]
+    fn probe() {
+        let w = Waypoints::new();
+        w.point(0, None).unwrap();
+        assert_eq!(w.probe(0), Probe::AlreadyPassed { current: 1 });
+        assert_eq!(w.probe(1), Probe::WouldPass);
+        assert_eq!(w.probe(4), Probe::WouldBlock { behind_by: 3 });
+        assert_eq!(w.passed_count(), 1);
+    }
+
+    #[test]
+    fn peak_waiters() {
+        let w = Waypoints::new_arc();
+        let threads: Vec<_> = (1..4)
+            .map(|i| {
+                let w = w.clone();
+                std::thread::spawn(move || w.point(i, None))
+            })
+            .collect();
+        while w.waiters() < 3 {
+            std::thread::yield_now();
+        }
+        w.point(0, None).unwrap();
+        threads.into_iter().for_each(|t| t.join().unwrap().unwrap());
+        assert_eq!(w.peak_waiters(), 3);
+        w.reset_peak();
+        assert_eq!(w.peak_waiters(), 0);
+    }
+
+    #[test]
+    fn wait_for_at_most() {
+        let w = Waypoints::new_arc();
+        (0..4).for_each(|i| w.point(i, None).unwrap());
+        let t = {
+            let w = w.clone();
+            std::thread::spawn(move || w.wait_for_at_most(1))
+        };
+        w.set_force(2, None).unwrap();
+        w.set_force(1, None).unwrap();
+        assert_eq!(t.join().unwrap(), 1);
+        assert_eq!(w.wait_for_at_most(3), 1);
+    }
+
+    #[test]
+    fn wait_generation() {
+        let w = Waypoints::new_arc();
+        let seen = w.generation();
+        let t = {
+            let w = w.clone();
+            std::thread::spawn(move || w.wait_generation(seen))
+        };
+        while w.state_lck().generation_waiters == 0 {
+            std::thread::yield_now();
+        }
+        w.point(0, None).unwrap();
+        assert_eq!(t.join().unwrap(), seen + 1);
+    }
+
+    #[test]
+    fn elapsed() {
+        let dt = Duration::from_millis(20);
+        let w = Waypoints::new();
+        std::thread::sleep(dt);
+        w.reset();
+        assert!(w.elapsed() >= dt);
+    }
+
+    #[test]
+    fn record_last() {
+        let w = Waypoints::new();
+        w.record_last(2);
+        (0..5).for_each(|i| w.point(i, None).unwrap());
+        let recent: Vec<_> = w.recent().iter().map(|e| e.n).collect();
+        assert_eq!(recent, vec![3, 4]);
+        assert!(w.events().is_empty());
+        w.record_last(0);
+        w.point(5, None).unwrap();
+        assert!(w.recent().is_empty());
+    }
+
+    #[test]
+    fn replay() {
+        let dt = Duration::from_millis(50);
+        let w = Waypoints::new();
+        w.record(true);
+        w.point(0, None).unwrap();
+        w.point(1, None).unwrap();
+        std::thread::sleep(dt);
+        w.point(2, None).unwrap();
+        let log = w.events();
+        assert_eq!(log.iter().map(|e| e.n).collect::<Vec<_>>(), vec![0, 1, 2]);
+
+        let w = Waypoints::replay(log);
+        w.point(0, None).unwrap();
+        w.point(1, None).unwrap();
+        let t0 = Instant::now();
+        w.point(2, None).unwrap();
+        assert!(t0.elapsed() > dt - Duration::from_millis(10));
+    }
+
+    #[test]
+    fn timed_out_progress() {
+        let w = Waypoints::new_arc();
+        let t = {
+            let w = w.clone();
+            std::thread::spawn(move || {
+                let deadline = Instant::now() + Duration::from_millis(100);
+                w.point_deadline(5, None, deadline)
+            })
+        };
+        while w.waiters() == 0 {
+            std::thread::yield_now();
+        }
+        w.point(0, None).unwrap();
+        let err = ErrorKind::TimedOut {
+            start_current: 0,
+            end_current: 1,
+            expected: 5,
+        };
+        assert_eq!(t.join().unwrap(), Err(err.into()));
+    }
+
+    #[test]
+    fn max_wait() {
+        let w = Waypoints::builder()
+            .max_wait(Duration::from_millis(20))
+            .build();
+        let res = w.point(1, None);
+        assert!(matches!(
+            res.unwrap_err().kind(),
+            ErrorKind::TimedOut { .. }
+        ));
+        w.point(0, None).unwrap();
+    }
+
+    #[test]
+    fn max_head_start_target() {
+        let max = Duration::from_millis(20);
+        let w = Waypoints::builder().max_head_start_target(max).build();
+        let t0 = Instant::now();
+        w.point(0, Duration::from_secs(3600)).unwrap();
+        w.point(1, Duration::from_secs(3600)).unwrap();
+        let scheduled = w.point_scheduled(2, Duration::from_secs(3600)).unwrap();
+        assert!(scheduled.is_some_and(|t| t <= Instant::now() + max));
+        assert!(t0.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn head_start_ratio() {
+        let w = Waypoints::builder().head_start_ratio(1.0).build();
+        let dt = Duration::from_millis(20);
+        w.point(0, None).unwrap();
+        std::thread::sleep(dt);
+        w.point(1, None).unwrap();
+        let scheduled = w.point_scheduled(2, None).unwrap();
+        assert!(scheduled.is_some());
+        let t0 = Instant::now();
+        w.point(3, Duration::ZERO).unwrap();
+        assert!(t0.elapsed() >= dt / 2);
+    }
+
+    #[test]
+    fn on_timeout() {
+        let w = Waypoints::builder()
+            .on_timeout(TimeoutAction::ReturnError)
+            .build();
+        let res = w.point_deadline(1, None, Instant::now());
+        assert!(matches!(
+            res.unwrap_err().kind(),
+            ErrorKind::TimedOut { .. }
+        ));
+        w.point(0, None).unwrap();
+    }
+
+    #[test]
+    fn dump_after() {
+        let dt = Duration::from_millis(20);
+        let w = Waypoints::builder().dump_after(dt, true).build();
+        let res = w.point(1, None);
+        assert!(matches!(
+            res.unwrap_err().kind(),
+            ErrorKind::TimedOut { .. }
+        ));
+
+        let w = Waypoints::builder().dump_after(dt, false).build_arc();
+        let t = {
+            let w = w.clone();
+            std::thread::spawn(move || w.point(1, None))
+        };
+        std::thread::sleep(2 * dt);
+        w.point(0, None).unwrap();
+        t.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn with_timeout() {
+        let w = Waypoints::new();
+        let other = Waypoints::new();
+        let res = w.with_timeout(Duration::from_millis(20), || {
+            other.point(0, None).unwrap();
+            w.point(1, None)
+        });
+        let err = ErrorKind::TimedOut {
+            start_current: 0,
+            end_current: 0,
+            expected: 1,
+        };
+        assert_eq!(res, Err(err.into()));
+        assert!(TIMEOUTS.with(|t| t.borrow().is_empty()));
+    }
+
+    #[test]
+    fn head_start_into() {
+        let w = Waypoints::new();
+        w.point(0, Duration::from_millis(1)).unwrap();
+        w.point(1, Some(Duration::from_millis(1))).unwrap();
+        w.point(2, None).unwrap();
+        w.range(3..4, Duration::from_millis(1)).unwrap();
+    }
+
+    #[test]
+    fn next_head_start() {
+        let dt = Duration::from_millis(50);
+        let w = Waypoints::new();
+        w.set_next_head_start(dt);
+        w.point(0, None).unwrap();
+        let t0 = Instant::now();
+        w.point(1, None).unwrap();
+        w.point(2, None).unwrap();
+        assert!(t0.elapsed() >= dt - Duration::from_millis(10));
+        assert_eq!(w.state_lck().target_time, None);
     }
 
     #[test]
@@ -332,7 +3964,7 @@ mod tests {
             assert_msg
         );
 
-        w.set(6, Some(Instant::now() + dt));
+        w.set(6, Some(Instant::now() + dt)).unwrap();
         w.point(6, Some(dt)).unwrap();
         let dt_observed = t0.elapsed();
         dt_n += 1;
@@ -0,0 +1,132 @@
+use crate::{WaypointError, Waypoints};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// [`Waypoints`] identified by string labels instead of numbers.
+///
+/// Each label is assigned the next waypoint number when it is registered, either by
+/// [`NamedWaypoints::new`] in the order of its slice or later with
+/// [`NamedWaypoints::register`], so the order of registration is the order of the waypoints.
+/// This replaces a comment mapping numbers to their meaning in tests with many ordering
+/// constraints.
+///
+/// ```
+/// use waypoints::NamedWaypoints;
+///
+/// let w = NamedWaypoints::new(&["spawn", "acquire", "release"]);
+/// std::thread::scope(|s| {
+///     s.spawn(|| w.point("release", None).unwrap());
+///     s.spawn(|| w.point("acquire", None).unwrap());
+///     w.point("spawn", None).unwrap();
+/// });
+/// assert!(w.point("unlock", None).is_err());
+/// ```
+#[derive(Debug, Default)]
+pub struct NamedWaypoints {
+    waypoints: Waypoints,
+    labels: Mutex<HashMap<String, usize>>,
+}
+
+impl NamedWaypoints {
+    /// Create `NamedWaypoints` with `labels` registered in order, starting at waypoint 0.
+    pub fn new(labels: &[&str]) -> Self {
+        let named = Self::default();
+        labels.iter().for_each(|label| {
+            named.register(label);
+        });
+        named
+    }
+
+    /// Create `NamedWaypoints` wrapped in an [`Arc`].
+    pub fn new_arc(labels: &[&str]) -> Arc<Self> {
+        Arc::new(Self::new(labels))
+    }
+
+    /// Assign the next waypoint number to `label` and return it, or return the number of `label`
+    /// if it was registered before.
+    pub fn register(&self, label: &str) -> usize {
+        let mut labels = Waypoints::into_guard(self.labels.lock());
+        let next = labels.len();
+        *labels.entry(label.to_string()).or_insert(next)
+    }
+
+    /// The waypoint number of `label`, if it was registered.
+    pub fn number(&self, label: &str) -> Option<usize> {
+        Waypoints::into_guard(self.labels.lock())
+            .get(label)
+            .copied()
+    }
+
+    /// Pass the waypoint of `label`.  See [`Waypoints::point`].  The `Result` is a
+    /// [`NamedError::UnknownLabel`] if `label` was not registered.
+    #[track_caller]
+    pub fn point(
+        &self,
+        label: &str,
+        head_start: impl Into<Option<Duration>>,
+    ) -> Result<(), NamedError> {
+        let n = self
+            .number(label)
+            .ok_or_else(|| NamedError::UnknownLabel(label.to_string()))?;
+        self.waypoints
+            .point(n, head_start)
+            .map_err(NamedError::Waypoint)
+    }
+
+    /// The underlying `Waypoints`, e.g. for inspecting the current number.
+    pub fn waypoints(&self) -> &Waypoints {
+        &self.waypoints
+    }
+}
+
+/// The error returned when a labeled waypoint of [`NamedWaypoints`] could not be passed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NamedError {
+    /// The label was not registered.  Contains the label.
+    UnknownLabel(String),
+    /// The waypoint of the label could not be passed.
+    Waypoint(WaypointError),
+}
+
+impl fmt::Display for NamedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownLabel(label) => write!(f, "unknown waypoint label {:?}", label),
+            Self::Waypoint(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for NamedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::UnknownLabel(_) => None,
+            Self::Waypoint(err) => Some(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ErrorKind;
+
+    #[test]
+    fn labels() {
+        let w = NamedWaypoints::new(&["a", "b"]);
+        assert_eq!(w.register("c"), 2);
+        assert_eq!(w.register("a"), 0);
+        w.point("a", None).unwrap();
+        assert_eq!(
+            w.point("a", None),
+            Err(NamedError::Waypoint(ErrorKind::AlreadyPassed(1).into()))
+        );
+        assert_eq!(
+            w.point("d", None),
+            Err(NamedError::UnknownLabel("d".to_string()))
+        );
+        assert_eq!(w.waypoints().passed_count(), 2);
+    }
+}
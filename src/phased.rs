@@ -0,0 +1,145 @@
+use crate::{
+    next_target_time, sleep_until, DefaultSleeper, ErrorKind, Sleeper, WaypointError, Waypoints,
+};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Represents a series of waypoints grouped into phases.
+///
+/// Each waypoint is identified by a `(phase, step)` pair.  Within a phase the steps are passed in
+/// order starting from step 0, like the numbers of [`Waypoints`];
+/// [`PhasedWaypoints::advance_phase`] moves to step 0 of the next phase.  Pairs are ordered
+/// lexicographically: `(p0, s0)` comes before `(p1, s1)` if `p0 < p1`, or if `p0 == p1` and
+/// `s0 < s1`.  A waypoint that comes after the current one blocks, and a waypoint that comes
+/// before it returns an `Err`.  This models an ordering that restarts in each phase without
+/// flattening it into a single number.
+#[derive(Debug, Default)]
+pub struct PhasedWaypoints {
+    state: Mutex<State>,
+    cv: Condvar,
+    sleeper: Option<Arc<dyn Sleeper>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    // the current `(phase, step)`
+    current: (usize, usize),
+    // the earliest time at which the next waypoint may be passed
+    target_time: Option<Instant>,
+}
+
+impl PhasedWaypoints {
+    /// Create `PhasedWaypoints` starting at step 0 of phase 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create `PhasedWaypoints` wrapped in an [`Arc`].
+    pub fn new_arc() -> Arc<Self> {
+        Arc::new(Self::new())
+    }
+
+    /// Create `PhasedWaypoints` that wait out head starts with `sleeper` instead of the
+    /// [`DefaultSleeper`], like [`Builder::sleeper`](crate::Builder::sleeper).
+    pub fn with_sleeper(sleeper: impl Sleeper + 'static) -> Self {
+        Self {
+            sleeper: Some(Arc::new(sleeper)),
+            ..Self::default()
+        }
+    }
+
+    /// Allow the waypoint to be passed once the current `(phase, step)` matches `key` exactly,
+    /// advancing to the next step in the phase.  See [`Waypoints::range`] for the `head_start`
+    /// argument.  The `Result` is an [`ErrorKind::PhaseAlreadyPassed`] containing the current
+    /// `(phase, step)` if `key` was already passed.
+    #[track_caller]
+    pub fn point(
+        &self,
+        key: (usize, usize),
+        head_start: impl Into<Option<Duration>>,
+    ) -> Result<(), WaypointError> {
+        let state_lck = self.cv.wait_while(self.state_lck(), |st| st.current < key);
+        let mut state_lck = Waypoints::into_guard(state_lck);
+        if state_lck.current != key {
+            let (phase, step) = state_lck.current;
+            return Err(ErrorKind::PhaseAlreadyPassed { phase, step }.into());
+        }
+
+        state_lck.current.1 += 1;
+        let now = Instant::now();
+        let target_time_this = state_lck.target_time;
+        state_lck.target_time = next_target_time(target_time_this, now, head_start.into());
+
+        // drop lock before sleeping
+        drop(state_lck);
+
+        let sleeper = self.sleeper.as_deref().unwrap_or(&DefaultSleeper);
+        sleep_until(target_time_this, now, sleeper);
+
+        self.cv.notify_all();
+
+        Ok(())
+    }
+
+    /// Move to step 0 of the next phase.  Waypoints of the current phase that were not passed yet
+    /// return an `Err`.
+    pub fn advance_phase(&self) {
+        let mut state_lck = self.state_lck();
+        state_lck.current = (state_lck.current.0 + 1, 0);
+        drop(state_lck);
+        self.cv.notify_all();
+    }
+
+    /// The current `(phase, step)`.
+    pub fn current(&self) -> (usize, usize) {
+        self.state_lck().current
+    }
+
+    fn state_lck(&self) -> MutexGuard<'_, State> {
+        Waypoints::into_guard(self.state.lock())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phases() {
+        let w = PhasedWaypoints::new_arc();
+        let t = {
+            let w = w.clone();
+            std::thread::spawn(move || {
+                w.point((1, 0), None).unwrap();
+                w.point((1, 2), None).unwrap();
+            })
+        };
+
+        w.point((0, 0), None).unwrap();
+        w.point((0, 1), None).unwrap();
+        w.advance_phase();
+        assert_eq!(
+            w.point((0, 2), None),
+            Err(ErrorKind::PhaseAlreadyPassed { phase: 1, step: 0 }.into())
+        );
+        w.point((1, 1), None).unwrap();
+        t.join().unwrap();
+        assert_eq!(w.current(), (1, 3));
+    }
+
+    #[test]
+    fn sleeper() {
+        #[derive(Debug)]
+        struct NoSleep;
+
+        impl Sleeper for NoSleep {
+            fn sleep(&self, _dt: Duration) {}
+        }
+
+        let w = PhasedWaypoints::with_sleeper(NoSleep);
+        let t0 = Instant::now();
+        w.point((0, 0), Duration::from_secs(10)).unwrap();
+        w.point((0, 1), None).unwrap();
+        assert!(t0.elapsed() < Duration::from_secs(10));
+    }
+}
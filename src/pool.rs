@@ -0,0 +1,103 @@
+use crate::Waypoints;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+/// A pool of reusable [`Waypoints`], for test suites that create a sequence per test case in a
+/// loop.
+///
+/// [`WaypointsPool::acquire`] hands out a guard dereferencing to an `Arc<Waypoints>` starting at
+/// waypoint 0.  Dropping the guard resets the `Waypoints` with [`Waypoints::reset_full`] and
+/// returns them to the pool, unless threads still hold clones of the `Arc`.  The configuration of
+/// the sequence, such as aliases, is not reset and carries over to the next use, so the pool is
+/// meant for plain sequences.
+///
+/// ```
+/// use waypoints::WaypointsPool;
+///
+/// let pool = WaypointsPool::new();
+/// for _ in 0..3 {
+///     let w = pool.acquire();
+///     w.point(0, None).unwrap();
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct WaypointsPool {
+    free: Mutex<Vec<Arc<Waypoints>>>,
+}
+
+impl WaypointsPool {
+    /// Create an empty pool.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take `Waypoints` from the pool, or create them if the pool is empty.
+    pub fn acquire(&self) -> PooledWaypoints<'_> {
+        let waypoints = Waypoints::into_guard(self.free.lock()).pop();
+        PooledWaypoints {
+            pool: self,
+            waypoints: Some(waypoints.unwrap_or_else(Waypoints::new_arc)),
+        }
+    }
+
+    /// The number of `Waypoints` available for reuse.
+    pub fn len(&self) -> usize {
+        Waypoints::into_guard(self.free.lock()).len()
+    }
+
+    /// Whether no `Waypoints` are available for reuse.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// `Waypoints` taken from a [`WaypointsPool`], returned to the pool when dropped.
+#[derive(Debug)]
+pub struct PooledWaypoints<'a> {
+    pool: &'a WaypointsPool,
+    // `None` only while dropping
+    waypoints: Option<Arc<Waypoints>>,
+}
+
+impl Deref for PooledWaypoints<'_> {
+    type Target = Arc<Waypoints>;
+
+    fn deref(&self) -> &Self::Target {
+        self.waypoints
+            .as_ref()
+            .expect("waypoints taken before drop")
+    }
+}
+
+impl Drop for PooledWaypoints<'_> {
+    fn drop(&mut self) {
+        let waypoints = self.waypoints.take().expect("waypoints taken before drop");
+        // threads that still use the `Waypoints` must not see them reset
+        if Arc::strong_count(&waypoints) == 1 {
+            waypoints.reset_full();
+            Waypoints::into_guard(self.pool.free.lock()).push(waypoints);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reuse() {
+        let pool = WaypointsPool::new();
+        let w = pool.acquire();
+        w.point(0, None).unwrap();
+        let addr = Arc::as_ptr(&w);
+        drop(w);
+        assert_eq!(pool.len(), 1);
+        let w = pool.acquire();
+        assert_eq!(Arc::as_ptr(&w), addr);
+        assert_eq!(w.passed_count(), 0);
+        let held = Arc::clone(&w);
+        drop(w);
+        assert!(pool.is_empty());
+        held.point(0, None).unwrap();
+    }
+}
@@ -0,0 +1,18 @@
+/// What [`Waypoints::point`](crate::Waypoints::point) would do for a waypoint at the time it was
+/// probed with [`Waypoints::probe`](crate::Waypoints::probe).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Probe {
+    /// The waypoint is the current one and would be passed without waiting (apart from a head
+    /// start).
+    WouldPass,
+    /// The waypoint would wait for `behind_by` other waypoints to be passed first.
+    WouldBlock {
+        /// The number of waypoints between the current one and the probed one.
+        behind_by: usize,
+    },
+    /// The waypoint was already passed and would return an `Err`.
+    AlreadyPassed {
+        /// The current waypoint number.
+        current: usize,
+    },
+}
@@ -0,0 +1,160 @@
+use std::fmt::Write;
+use std::thread::ThreadId;
+use std::time::{Duration, Instant};
+
+/// A waypoint passage recorded while [`Waypoints::record`](crate::Waypoints::record) is enabled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventRecord {
+    /// The waypoint number that was passed.
+    pub n: usize,
+    /// The time at which the thread started waiting on the waypoint.
+    pub arrived: Instant,
+    /// The time at which the waypoint was passed, including any head start it had to wait for.
+    pub passed: Instant,
+    /// The thread that passed the waypoint.
+    pub thread: ThreadId,
+    /// The name of the thread that passed the waypoint.
+    pub thread_name: Option<String>,
+}
+
+impl EventRecord {
+    pub(crate) fn new(n: usize, arrived: Instant, passed: Instant) -> Self {
+        let thread = std::thread::current();
+        Self {
+            n,
+            arrived,
+            passed,
+            thread: thread.id(),
+            thread_name: thread.name().map(String::from),
+        }
+    }
+}
+
+/// An [`EventRecord`] tagged with the sequence it was recorded by, returned by [`merge_logs`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergedRecord {
+    /// The index of the log of the sequence in the argument of [`merge_logs`].
+    pub sequence: usize,
+    /// The recorded passage.
+    pub record: EventRecord,
+}
+
+/// Interleave the recorded events of several sequences (e.g. the
+/// [`Waypoints::events`](crate::Waypoints::events) of each) by the time at which the waypoints
+/// were passed, tagging each event with the index of its log in `logs`.  This shows how several
+/// orderings interacted during a test on a single timeline.  Events passed at the same time keep
+/// the order of `logs`.
+///
+/// ```
+/// use waypoints::{merge_logs, Waypoints};
+///
+/// let (a, b) = (Waypoints::new(), Waypoints::new());
+/// a.record(true);
+/// b.record(true);
+/// a.point(0, None).unwrap();
+/// b.point(0, None).unwrap();
+/// a.point(1, None).unwrap();
+/// let merged = merge_logs(&[&a.events(), &b.events()]);
+/// let order: Vec<_> = merged.iter().map(|m| (m.sequence, m.record.n)).collect();
+/// assert_eq!(order, vec![(0, 0), (1, 0), (0, 1)]);
+/// ```
+pub fn merge_logs(logs: &[&[EventRecord]]) -> Vec<MergedRecord> {
+    let mut merged: Vec<_> = logs
+        .iter()
+        .enumerate()
+        .flat_map(|(sequence, log)| {
+            log.iter().map(move |record| MergedRecord {
+                sequence,
+                record: record.clone(),
+            })
+        })
+        .collect();
+    merged.sort_by_key(|m| m.record.passed);
+    merged
+}
+
+// the minimum delay between passing waypoint `n - 1` and waypoint `n`, indexed by `n`
+pub(crate) fn gaps(mut log: Vec<EventRecord>) -> Vec<Duration> {
+    log.sort_by_key(|e| e.n);
+    let mut gaps = vec![Duration::from_secs(0); log.last().map_or(0, |e| e.n + 1)];
+    for w in log.windows(2) {
+        if w[0].n + 1 == w[1].n {
+            gaps[w[1].n] = w[1].passed.saturating_duration_since(w[0].passed);
+        }
+    }
+    gaps
+}
+
+// the events as Trace Event Format JSON, with times in microseconds since `start`
+pub(crate) fn chrome_trace(log: &[EventRecord], start: Instant) -> String {
+    let micros = |t: Instant| t.saturating_duration_since(start).as_micros();
+    let mut threads: Vec<&EventRecord> = Vec::new();
+    let mut events = Vec::new();
+    for e in log {
+        let tid = match threads.iter().position(|t| t.thread == e.thread) {
+            Some(tid) => tid,
+            None => {
+                threads.push(e);
+                threads.len() - 1
+            }
+        };
+        events.push(format!(
+            r#"{{"name":"waypoint {}","ph":"X","pid":0,"tid":{},"ts":{},"dur":{}}}"#,
+            e.n,
+            tid,
+            micros(e.arrived),
+            e.passed.saturating_duration_since(e.arrived).as_micros(),
+        ));
+    }
+    events.push(r#"{"name":"process_name","ph":"M","pid":0,"args":{"name":"waypoints"}}"#.into());
+    for (tid, e) in threads.iter().enumerate() {
+        let name = match &e.thread_name {
+            Some(name) => escape(name),
+            None => format!("{:?}", e.thread),
+        };
+        events.push(format!(
+            r#"{{"name":"thread_name","ph":"M","pid":0,"tid":{},"args":{{"name":"{}"}}}}"#,
+            tid, name
+        ));
+    }
+    format!(r#"{{"traceEvents":[{}]}}"#, events.join(","))
+}
+
+// escape a string for a JSON string literal
+fn escape(s: &str) -> String {
+    let mut escaped = String::new();
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                write!(escaped, "\\u{:04x}", c as u32).expect("write to a String");
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chrome_trace() {
+        let start = Instant::now();
+        let t = start + Duration::from_micros(10);
+        let mut e = EventRecord::new(3, t, t + Duration::from_micros(5));
+        e.thread_name = Some("a \"b\"".into());
+        assert_eq!(
+            super::chrome_trace(&[e], start),
+            concat!(
+                r#"{"traceEvents":["#,
+                r#"{"name":"waypoint 3","ph":"X","pid":0,"tid":0,"ts":10,"dur":5},"#,
+                r#"{"name":"process_name","ph":"M","pid":0,"args":{"name":"waypoints"}},"#,
+                r#"{"name":"thread_name","ph":"M","pid":0,"tid":0,"args":{"name":"a \"b\""}}"#,
+                "]}"
+            )
+        );
+    }
+}
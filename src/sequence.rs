@@ -0,0 +1,201 @@
+use crate::{WaypointError, Waypoints};
+use std::time::Duration;
+
+/// The waypoints passed by a single thread, in the order in which the thread passes them.
+///
+/// Created by [`Waypoints::thread_sequence`] or the [`wp_thread!`](crate::wp_thread) macro, and
+/// usually passed with the [`wp!`](crate::wp) macro.  The order across threads is still defined
+/// by the waypoint numbers; a `ThreadSequence` only saves repeating the numbers at each call site
+/// of a thread.  See [`Turns`] for numbering the waypoints automatically.
+#[derive(Debug)]
+pub struct ThreadSequence<'a> {
+    waypoints: &'a Waypoints,
+    numbers: Vec<usize>,
+    next: usize,
+}
+
+impl<'a> ThreadSequence<'a> {
+    pub(crate) fn new(waypoints: &'a Waypoints, numbers: Vec<usize>) -> Self {
+        Self {
+            waypoints,
+            numbers,
+            next: 0,
+        }
+    }
+
+    /// Pass the next waypoint of the sequence.  See [`Waypoints::point`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if all waypoints of the sequence were passed already.
+    #[track_caller]
+    pub fn point(&mut self, head_start: impl Into<Option<Duration>>) -> Result<(), WaypointError> {
+        let n = *self
+            .numbers
+            .get(self.next)
+            .expect("no waypoints left in thread sequence");
+        self.next += 1;
+        self.waypoints.point(n, head_start)
+    }
+
+    /// The waypoints of the sequence that were not passed yet.
+    pub fn remaining(&self) -> &[usize] {
+        &self.numbers[self.next..]
+    }
+}
+
+/// The waypoints passed by one of several threads that take turns, numbered automatically from the
+/// order in which the thread passes them.
+///
+/// Created by [`Waypoints::turns`] or the [`wp_thread!`](crate::wp_thread) macro, and usually
+/// passed with the [`wp!`](crate::wp) macro.  Thread `thread` of `threads` passes waypoint
+/// `step * threads + thread` at its `step`-th call, so the order across threads is well-defined
+/// without writing any numbers: all threads pass their first waypoint in the order of their
+/// `thread` indices, then all threads pass their second waypoint in the same order, and so on.
+/// Every thread has to pass the same number of waypoints, since a thread that stops early stalls
+/// the threads whose later turns come after its missing ones.
+#[derive(Debug)]
+pub struct Turns<'a> {
+    waypoints: &'a Waypoints,
+    thread: usize,
+    threads: usize,
+    step: usize,
+}
+
+impl<'a> Turns<'a> {
+    pub(crate) fn new(waypoints: &'a Waypoints, thread: usize, threads: usize) -> Self {
+        assert!(
+            thread < threads,
+            "thread {} out of range for {} threads",
+            thread,
+            threads
+        );
+        Self {
+            waypoints,
+            thread,
+            threads,
+            step: 0,
+        }
+    }
+
+    /// Pass the waypoint of the next turn.  See [`Waypoints::point`].
+    #[track_caller]
+    pub fn point(&mut self, head_start: impl Into<Option<Duration>>) -> Result<(), WaypointError> {
+        let n = self.next();
+        self.step += 1;
+        self.waypoints.point(n, head_start)
+    }
+
+    /// The waypoint number of the next turn.
+    pub fn next(&self) -> usize {
+        self.step * self.threads + self.thread
+    }
+}
+
+/// Generate the waypoint numbers of `threads` threads programmatically: thread `t` passes
+/// `f(t, step)` for each `step` in `0..steps` for which it returns `Some`, in order of `step`.
+/// The numbers of each thread can be passed to
+/// [`Waypoints::thread_sequence`](crate::Waypoints::thread_sequence), which replaces
+/// hand-maintained tables of numbers in tests that sweep over thread and step counts.
+///
+/// ```
+/// use waypoints::{assign, Waypoints};
+///
+/// // round-robin: thread `t` passes every `threads`-th waypoint starting at `t`
+/// let threads = 3;
+/// let numbers = assign(threads, 2, |t, step| Some(step * threads + t));
+/// assert_eq!(numbers, vec![vec![0, 3], vec![1, 4], vec![2, 5]]);
+///
+/// let w = Waypoints::new();
+/// std::thread::scope(|s| {
+///     for numbers in numbers {
+///         let w = &w;
+///         s.spawn(move || {
+///             let mut seq = w.thread_sequence(numbers);
+///             while !seq.remaining().is_empty() {
+///                 seq.point(None).unwrap();
+///             }
+///         });
+///     }
+/// });
+/// assert_eq!(w.passed_count(), 6);
+/// ```
+pub fn assign(
+    threads: usize,
+    steps: usize,
+    f: impl Fn(usize, usize) -> Option<usize>,
+) -> Vec<Vec<usize>> {
+    (0..threads)
+        .map(|t| (0..steps).filter_map(|step| f(t, step)).collect())
+        .collect()
+}
+
+/// Declare the waypoints passed by the current thread, in order, returning a [`ThreadSequence`],
+/// or declare the thread as one of several that take turns, returning a [`Turns`] that numbers
+/// the waypoints automatically.
+///
+/// With explicit numbers, the numbers define the order across threads:
+///
+/// ```
+/// use waypoints::{wp, wp_thread, Waypoints};
+///
+/// let w = Waypoints::new();
+/// std::thread::scope(|s| {
+///     s.spawn(|| {
+///         let mut seq = wp_thread!(w; 1, 2);
+///         wp!(seq);
+///         wp!(seq);
+///     });
+///     let mut seq = wp_thread!(w; 0, 3);
+///     wp!(seq);
+///     wp!(seq);
+/// });
+/// ```
+///
+/// With `turn = t, threads = n`, the thread only marks its waypoints with [`wp!`](crate::wp) in
+/// source order, and its `step`-th mark passes waypoint `step * n + t` (see [`Turns`] for how
+/// this orders the threads).  The example passes the same waypoints 0 to 3 as the one above:
+///
+/// ```
+/// use waypoints::{wp, wp_thread, Waypoints};
+///
+/// let w = Waypoints::new();
+/// std::thread::scope(|s| {
+///     s.spawn(|| {
+///         let mut seq = wp_thread!(w; turn = 1, threads = 2);
+///         wp!(seq);
+///         wp!(seq);
+///     });
+///     let mut seq = wp_thread!(w; turn = 0, threads = 2);
+///     wp!(seq);
+///     wp!(seq);
+/// });
+/// assert_eq!(w.passed_count(), 4);
+/// ```
+#[macro_export]
+macro_rules! wp_thread {
+    ($waypoints:expr; turn = $t:expr, threads = $threads:expr $(,)?) => {
+        $waypoints.turns($t, $threads)
+    };
+    ($waypoints:expr; $($n:expr),* $(,)?) => {
+        $waypoints.thread_sequence(vec![$($n),*])
+    };
+}
+
+/// Pass the next waypoint of a [`ThreadSequence`] or [`Turns`], optionally with a head start.  See
+/// [`wp_thread!`](crate::wp_thread) for examples.
+///
+/// # Panics
+///
+/// Panics if the waypoint was already passed or a `ThreadSequence` has no waypoints left.
+#[macro_export]
+macro_rules! wp {
+    ($seq:expr) => {
+        $crate::wp!($seq, None)
+    };
+    ($seq:expr, $head_start:expr) => {
+        if let Err(err) = $seq.point($head_start) {
+            panic!("{}", err);
+        }
+    };
+}
@@ -0,0 +1,44 @@
+use std::fmt;
+use std::time::Duration;
+
+/// Waits out the head starts of waypoints.
+///
+/// A `Sleeper` is set with [`Builder::sleeper`](crate::Builder::sleeper) to change how a thread
+/// waits until the next waypoint may be passed, e.g. to sleep more accurately, not to sleep at all
+/// in tests that only care about the order, or to bridge to another timer.
+///
+/// ```
+/// use std::time::{Duration, Instant};
+/// use waypoints::{Sleeper, Waypoints};
+///
+/// #[derive(Debug)]
+/// struct NoSleep;
+///
+/// impl Sleeper for NoSleep {
+///     fn sleep(&self, _dt: Duration) {}
+/// }
+///
+/// let w = Waypoints::builder().sleeper(NoSleep).build();
+/// let t0 = Instant::now();
+/// w.point(0, Duration::from_secs(10)).unwrap();
+/// w.point(1, None).unwrap();
+/// assert!(t0.elapsed() < Duration::from_secs(10));
+/// ```
+pub trait Sleeper: fmt::Debug + Send + Sync {
+    /// Block the current thread for `dt`.
+    fn sleep(&self, dt: Duration);
+}
+
+/// The [`Sleeper`] used unless another one is configured.  It sleeps with
+/// [`std::thread::sleep`], or with `spin_sleep` if the `spin-sleep` feature is enabled.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DefaultSleeper;
+
+impl Sleeper for DefaultSleeper {
+    fn sleep(&self, dt: Duration) {
+        #[cfg(feature = "spin-sleep")]
+        spin_sleep::sleep(dt);
+        #[cfg(not(feature = "spin-sleep"))]
+        std::thread::sleep(dt);
+    }
+}
@@ -0,0 +1,34 @@
+use crate::{State, Stats};
+use std::time::Instant;
+
+/// The state of [`Waypoints`](crate::Waypoints) at one point in time, returned by
+/// [`Waypoints::try_snapshot`](crate::Waypoints::try_snapshot).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WaypointsSnapshot {
+    /// The number of the next waypoint.
+    pub current: usize,
+    /// The earliest time at which the next waypoint may be passed, if it is constrained.
+    pub target_time: Option<Instant>,
+    /// The waypoint numbers the waiting threads wait for in ascending order, once per waiting
+    /// thread.  The order in which the threads started waiting is only exposed by
+    /// `Waypoints::waiter_order` with the `test-internals` feature.
+    pub waiting: Vec<usize>,
+    /// The number of times the current number changed.
+    pub generation: u64,
+    /// The counters of [`Waypoints::stats`](crate::Waypoints::stats).
+    pub stats: Stats,
+}
+
+impl WaypointsSnapshot {
+    pub(crate) fn new(state: &State) -> Self {
+        let mut waiting: Vec<_> = state.waiters.values().copied().collect();
+        waiting.sort_unstable();
+        Self {
+            current: state.n,
+            target_time: state.target_time,
+            waiting,
+            generation: state.generation,
+            stats: state.stats,
+        }
+    }
+}
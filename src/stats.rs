@@ -0,0 +1,15 @@
+/// Counters describing how threads were woken, returned by
+/// [`Waypoints::stats`](crate::Waypoints::stats).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    /// The number of times waiting threads were notified of a change of state.
+    pub notifications: u64,
+    /// The number of times passing a waypoint did not notify waiting threads because none of
+    /// them waits for a waypoint that was reached.
+    pub skipped_notifications: u64,
+    /// The number of times a waiting thread woke up to re-check the state.
+    pub wakeups: u64,
+    /// The number of waypoints passed out of order with
+    /// [`Builder::soft_order`](crate::Builder::soft_order) enabled.
+    pub out_of_order: u64,
+}
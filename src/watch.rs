@@ -0,0 +1,216 @@
+use crate::{Cond, ErrorKind, WaypointError, Waypoints};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Instant;
+
+// a condition variable that several `Waypoints` signal whenever their state changes
+#[derive(Debug, Default)]
+pub(crate) struct Watch {
+    // incremented on each signal
+    generation: Mutex<u64>,
+    cv: Condvar,
+}
+
+impl Watch {
+    pub(crate) fn signal(&self) {
+        *Waypoints::into_guard(self.generation.lock()) += 1;
+        self.cv.notify_all();
+    }
+
+    fn generation(&self) -> u64 {
+        *Waypoints::into_guard(self.generation.lock())
+    }
+
+    fn wait_while_generation(&self, generation: u64) {
+        let lck = self
+            .cv
+            .wait_while(Waypoints::into_guard(self.generation.lock()), |g| {
+                *g == generation
+            });
+        drop(Waypoints::into_guard(lck));
+    }
+}
+
+/// Block until any of several [`Waypoints`] reaches its target, i.e. until the current number of
+/// `targets[i].0` is at least `targets[i].1`, and return the index `i`.  If several targets are
+/// reached, the lowest index is returned.  This allows coordinating with whichever of several
+/// independent sequences gets there first.
+///
+/// ```
+/// use waypoints::{wait_any, Waypoints};
+///
+/// let (a, b) = (Waypoints::new(), Waypoints::new());
+/// b.point(0, None).unwrap();
+/// assert_eq!(wait_any(&[(&a, 1), (&b, 1)]), 1);
+/// ```
+///
+/// # Panics
+///
+/// Panics if `targets` is empty.
+pub fn wait_any(targets: &[(&Waypoints, usize)]) -> usize {
+    assert!(!targets.is_empty(), "no targets to wait for");
+    let watch = Arc::new(Watch::default());
+    targets
+        .iter()
+        .for_each(|(w, _)| w.state_lck().watchers.push(watch.clone()));
+
+    let reached = loop {
+        let generation = watch.generation();
+        if let Some(i) = targets.iter().position(|&(w, n)| w.state_lck().n >= n) {
+            break i;
+        }
+        watch.wait_while_generation(generation);
+    };
+
+    targets.iter().for_each(|(w, _)| {
+        w.state_lck()
+            .watchers
+            .retain(|other| !Arc::ptr_eq(other, &watch))
+    });
+    reached
+}
+
+/// Block until every one of several [`Waypoints`] reaches its target, i.e. until the current
+/// number of `targets[i].0` is exactly `targets[i].1`, then pass the target waypoint of all of them
+/// together.  This models a join point where a thread must be next on several independent
+/// sequences at once, which would race if the waypoints were passed one after another.
+///
+/// To avoid deadlocks between threads calling `wait_all` with overlapping targets, the states of
+/// the `Waypoints` are always locked in the order of their addresses.  If a sequence has not
+/// reached its target yet, all locks are released and the thread waits for that sequence alone
+/// before locking all of them again and re-checking, since the other sequences may have changed
+/// in the meantime.  Each waypoint is passed without a head start of its own, but otherwise like
+/// one passed with [`Waypoints::point`]: it is recorded (see [`Waypoints::record`]), forwarded
+/// to tees, enters phases and is counted.  The threads waiting on every sequence are woken
+/// before the calling thread waits out the head starts given for the waypoints, so waiting on
+/// one sequence is not held up by the head start of another.
+///
+/// ```
+/// use waypoints::{wait_all, Waypoints};
+///
+/// let (a, b) = (Waypoints::new(), Waypoints::new());
+/// a.point(0, None).unwrap();
+/// wait_all(&[(&a, 1), (&b, 0)]).unwrap();
+/// assert_eq!((a.passed_count(), b.passed_count()), (2, 1));
+/// ```
+///
+/// The `Result` is an [`ErrorKind::AlreadyPassed`] with the current number of the first
+/// sequence found past its target, or an [`ErrorKind::Cancelled`] if any of the sequences is
+/// cancelled with [`Waypoints::cancel`] before or while waiting, in which case no waypoint is
+/// passed.
+///
+/// # Panics
+///
+/// Panics if the same `Waypoints` are listed more than once.
+#[track_caller]
+pub fn wait_all(targets: &[(&Waypoints, usize)]) -> Result<(), WaypointError> {
+    let mut order: Vec<_> = targets.iter().collect();
+    order.sort_by_key(|(w, _)| w.addr());
+    assert!(
+        order.windows(2).all(|p| p[0].0.addr() != p[1].0.addr()),
+        "the same waypoints are listed more than once"
+    );
+
+    let arrived = Instant::now();
+    let mut passages = loop {
+        let mut guards: Vec<_> = order.iter().map(|(w, _)| w.state_lck()).collect();
+        if guards.iter().any(|st| st.cancelled) {
+            return Err(ErrorKind::Cancelled.into());
+        }
+        let behind = order.iter().zip(&guards).find(|((_, n), st)| st.n != *n);
+        match behind {
+            Some(((_, n), st)) if st.n > *n => return Err(ErrorKind::AlreadyPassed(st.n).into()),
+            Some(((w, n), _)) => {
+                drop(guards);
+                drop(w.wait_for(w.state_lck(), *n, None, Cond::default()));
+                continue;
+            }
+            None => {}
+        }
+
+        let passages: Vec<_> = order
+            .iter()
+            .zip(&mut guards)
+            .map(|((w, _), state)| (*w, w.pass_locked(state, 1, true, arrived, None)))
+            .collect();
+        break passages;
+    };
+
+    // the threads waiting on one sequence must not wait for the head starts of the others
+    passages.iter_mut().for_each(|(w, p)| p.finish(w));
+    passages.iter().for_each(|(w, p)| p.sleep(w));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wait_all() {
+        let (a, b) = (Waypoints::new_arc(), Waypoints::new_arc());
+        b.record(true);
+        let t = {
+            let (a, b) = (a.clone(), b.clone());
+            std::thread::spawn(move || super::wait_all(&[(&b, 1), (&a, 2)]))
+        };
+        a.point(0, None).unwrap();
+        b.point(0, None).unwrap();
+        a.point(1, None).unwrap();
+        t.join().unwrap().unwrap();
+        assert_eq!((a.passed_count(), b.passed_count()), (3, 2));
+        assert_eq!((a.total_advances(), a.passed()), (3, 3));
+        assert_eq!((b.total_advances(), b.passed()), (2, 2));
+        let passed: Vec<_> = b.events().iter().map(|e| e.n).collect();
+        assert_eq!(passed, vec![0, 1]);
+        assert_eq!(
+            super::wait_all(&[(&a, 3), (&b, 1)]),
+            Err(ErrorKind::AlreadyPassed(2).into())
+        );
+        assert_eq!(a.passed_count(), 3);
+    }
+
+    #[test]
+    fn wait_all_head_start() {
+        let (a, b) = (Waypoints::new_arc(), Waypoints::new_arc());
+        let t = {
+            let b = b.clone();
+            std::thread::spawn(move || {
+                b.point(1, None).unwrap();
+                Instant::now()
+            })
+        };
+        while b.waiters() == 0 {
+            std::thread::yield_now();
+        }
+        a.point(0, std::time::Duration::from_millis(100)).unwrap();
+        super::wait_all(&[(&a, 1), (&b, 0)]).unwrap();
+        let returned = Instant::now();
+        assert!(t.join().unwrap() < returned);
+    }
+
+    #[test]
+    fn wait_all_cancelled_behind() {
+        let (a, b) = (Waypoints::new_arc(), Waypoints::new_arc());
+        let t = {
+            let (a, b) = (a.clone(), b.clone());
+            std::thread::spawn(move || super::wait_all(&[(&a, 1), (&b, 0)]))
+        };
+        while a.waiters() == 0 {
+            std::thread::yield_now();
+        }
+        a.cancel();
+        assert_eq!(t.join().unwrap(), Err(ErrorKind::Cancelled.into()));
+        assert_eq!(b.current(), 0);
+    }
+
+    #[test]
+    fn wait_all_cancelled_at_target() {
+        let (a, b) = (Waypoints::new(), Waypoints::new());
+        b.cancel();
+        assert_eq!(
+            super::wait_all(&[(&a, 0), (&b, 0)]),
+            Err(ErrorKind::Cancelled.into())
+        );
+        assert_eq!((a.current(), b.current()), (0, 0));
+    }
+}
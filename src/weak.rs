@@ -0,0 +1,76 @@
+use crate::{ErrorKind, Pass, Waypoint, WaypointError, Waypoints};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+
+// how often a waiting `WeakWaypoints::point` checks whether the `Waypoints` were dropped
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A handle to [`Waypoints`] that does not keep them alive, created by [`Waypoints::downgrade`].
+///
+/// This lets detached threads synchronize with a sequence as long as it exists, and give up
+/// instead of hanging once the rest of the test dropped it.
+#[derive(Debug, Clone)]
+pub struct WeakWaypoints {
+    waypoints: Weak<Waypoints>,
+}
+
+impl WeakWaypoints {
+    pub(crate) fn new(waypoints: Weak<Waypoints>) -> Self {
+        Self { waypoints }
+    }
+
+    /// The `Waypoints`, if they were not dropped yet.
+    pub fn upgrade(&self) -> Option<Arc<Waypoints>> {
+        self.waypoints.upgrade()
+    }
+
+    /// Same as [`Waypoints::point`], but return an [`ErrorKind::Gone`] if the `Waypoints` are
+    /// dropped.  To notice that, the thread does not keep the `Waypoints` alive while it waits:
+    /// it waits in slices of a few milliseconds, releasing its reference in between, so a wait
+    /// may end up to one slice after the last other reference was dropped.  A deadline of
+    /// [`Waypoints::with_timeout`] or [`Builder::max_wait`](crate::Builder::max_wait) does not
+    /// apply, since each slice has a deadline of its own.
+    #[track_caller]
+    pub fn point(
+        &self,
+        n: impl Waypoint,
+        head_start: impl Into<Option<Duration>>,
+    ) -> Result<(), WaypointError> {
+        let n = n.ordinal();
+        let head_start = head_start.into();
+        loop {
+            let waypoints = match self.upgrade() {
+                Some(waypoints) => waypoints,
+                None => return Err(ErrorKind::Gone.into()),
+            };
+            let n = waypoints.state_lck().resolve(n);
+            let pass = Pass {
+                deadline: Some(Instant::now() + POLL_INTERVAL),
+                poll: true,
+                ..Pass::new(n..n + 1, head_start)
+            };
+            match waypoints.advance(pass).map(drop) {
+                Err(err) if matches!(err.kind(), ErrorKind::TimedOut { .. }) => {}
+                res => return res,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gone() {
+        let w = Waypoints::new_arc();
+        let weak = w.downgrade();
+        let t = std::thread::spawn(move || weak.point(2, None));
+        w.point(0, None).unwrap();
+        while w.waiters() == 0 {
+            std::thread::yield_now();
+        }
+        drop(w);
+        assert_eq!(t.join().unwrap(), Err(ErrorKind::Gone.into()));
+    }
+}
@@ -0,0 +1,80 @@
+//! Derive macro for the `Waypoint` trait of the [`waypoints`][waypoints_url] crate.  Use it through
+//! the `derive` feature of `waypoints` rather than depending on this crate directly.
+//!
+//! [waypoints_url]: https://trtsl.github.io/waypoints/waypoints/index.html
+
+#![forbid(unsafe_code)]
+#![warn(rust_2018_idioms, missing_debug_implementations, missing_docs)]
+
+use proc_macro::{Delimiter, TokenStream, TokenTree};
+
+/// Implement `waypoints::Waypoint` for an enum with unit variants, numbering the variants in the
+/// order in which they are declared, starting from 0.
+#[proc_macro_derive(Waypoint)]
+pub fn derive_waypoint(input: TokenStream) -> TokenStream {
+    match parse_enum(input) {
+        Ok((name, variants)) => implement(&name, &variants),
+        Err(msg) => format!("compile_error!({:?});", msg).parse().unwrap(),
+    }
+}
+
+// the name and the variants of an enum with unit variants
+fn parse_enum(input: TokenStream) -> Result<(String, Vec<String>), String> {
+    let mut tokens = input.into_iter();
+    // skip attributes and visibility up to the `enum` keyword
+    let name = loop {
+        match tokens.next() {
+            Some(TokenTree::Ident(ident)) if ident.to_string() == "enum" => match tokens.next() {
+                Some(TokenTree::Ident(name)) => break name.to_string(),
+                _ => return Err("expected the name of the enum".into()),
+            },
+            Some(TokenTree::Ident(ident)) if ident.to_string() == "struct" => {
+                return Err("`Waypoint` can only be derived for enums".into())
+            }
+            Some(TokenTree::Ident(ident)) if ident.to_string() == "union" => {
+                return Err("`Waypoint` can only be derived for enums".into())
+            }
+            Some(_) => {}
+            None => return Err("expected an enum".into()),
+        }
+    };
+    let body = match tokens.next() {
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Brace => group.stream(),
+        _ => return Err("`Waypoint` cannot be derived for generic enums".into()),
+    };
+
+    let mut variants = Vec::new();
+    let mut expect_variant = true;
+    for token in body {
+        match token {
+            // attributes of a variant
+            TokenTree::Punct(p) if p.as_char() == '#' => {}
+            TokenTree::Group(g) if g.delimiter() == Delimiter::Bracket => {}
+            TokenTree::Ident(ident) if expect_variant => {
+                variants.push(ident.to_string());
+                expect_variant = false;
+            }
+            TokenTree::Punct(p) if p.as_char() == ',' => expect_variant = true,
+            _ => return Err("`Waypoint` can only be derived for enums with unit variants".into()),
+        }
+    }
+    Ok((name, variants))
+}
+
+fn implement(name: &str, variants: &[String]) -> TokenStream {
+    let arms: String = variants
+        .iter()
+        .enumerate()
+        .map(|(i, v)| format!("{}::{} => {},", name, v, i))
+        .collect();
+    format!(
+        "impl ::waypoints::Waypoint for {} {{
+            fn ordinal(&self) -> usize {{
+                match *self {{ {} }}
+            }}
+        }}",
+        name, arms
+    )
+    .parse()
+    .unwrap()
+}